@@ -0,0 +1,429 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::Sender;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::core::UIAutomation;
+use super::core::UIElement;
+use super::core::UIMatcher;
+use super::errors::ERR_INVALID_OBJECT;
+use super::errors::ERR_NOTFOUND;
+use super::errors::ERR_TIMEOUT;
+use super::errors::Error;
+use super::errors::Result;
+
+type Attempt = Box<dyn FnMut(&UIAutomation) -> Option<Duration>>;
+type Job = Box<dyn FnOnce(&UIAutomation) -> Attempt + Send>;
+
+/// Runs UI Automation calls on a dedicated multithreaded apartment thread and exposes them as futures.
+///
+/// The futures do not depend on any async runtime, so they can be awaited in `tokio`, `async-std` or any other executor.
+/// Retries are scheduled on the apartment thread, so no executor thread is blocked while waiting.
+///
+/// A task is cancelled when its future is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::asyncs::AsyncAutomation;
+///
+/// async fn find_notepad() -> uiautomation::Result<String> {
+///     let automation = AsyncAutomation::new()?;
+///     let notepad = automation.find_first_async(|a| a.create_matcher().classname("Notepad").timeout(10000)).await?;
+///     notepad.get_name()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AsyncAutomation {
+    sender: Sender<Job>
+}
+
+impl AsyncAutomation {
+    /// Starts the apartment thread and creates a uiautomation client instance on it.
+    ///
+    /// The thread stops after all clones of `AsyncAutomation` and all pending tasks are dropped.
+    pub fn new() -> Result<Self> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let (init_sender, init_receiver) = mpsc::channel::<Result<()>>();
+
+        thread::Builder::new()
+            .name("uiautomation-async".into())
+            .spawn(move || {
+                let automation = match UIAutomation::new() {
+                    Ok(automation) => automation,
+                    Err(e) => {
+                        let _ = init_sender.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = init_sender.send(Ok(()));
+
+                Self::run_loop(&automation, receiver);
+            })
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        init_receiver.recv().map_err(|e| Error::from(e.to_string()))??;
+
+        Ok(Self {
+            sender
+        })
+    }
+
+    /// Runs `f` once on the apartment thread.
+    pub fn run<R, F>(&self, f: F) -> UITask<R> where R: Send + 'static, F: FnOnce(&UIAutomation) -> Result<R> + Send + 'static {
+        self.schedule(move |_| {
+            let mut f = Some(f);
+            move |automation: &UIAutomation| {
+                match f.take() {
+                    Some(f) => ControlFlow::Break(f(automation)),
+                    None => ControlFlow::Break(Err(Error::new(ERR_INVALID_OBJECT, "task is already finished")))
+                }
+            }
+        })
+    }
+
+    /// Finds the first element matched by the `UIMatcher` created by `builder`.
+    ///
+    /// The `timeout` and `interval` of the matcher are used for retrying.
+    pub fn find_first_async<F>(&self, builder: F) -> UITask<SendElement> where F: FnOnce(&UIAutomation) -> UIMatcher + Send + 'static {
+        self.schedule(move |automation| {
+            let mut attempt = Self::find_attempt(builder(automation), Some(1));
+            move |automation: &UIAutomation| {
+                attempt(automation).map_break(|r| r.map(|mut elements| SendElement(elements.swap_remove(0))))
+            }
+        })
+    }
+
+    /// Finds all the elements matched by the `UIMatcher` created by `builder`.
+    ///
+    /// The `timeout` and `interval` of the matcher are used for retrying.
+    pub fn find_all_async<F>(&self, builder: F) -> UITask<Vec<SendElement>> where F: FnOnce(&UIAutomation) -> UIMatcher + Send + 'static {
+        self.schedule(move |automation| {
+            let mut attempt = Self::find_attempt(builder(automation), None);
+            move |automation: &UIAutomation| {
+                attempt(automation).map_break(|r| r.map(|elements| elements.into_iter().map(SendElement).collect()))
+            }
+        })
+    }
+
+    /// Waits until `predicate` returns `true`, checking it every `interval` millionseconds.
+    ///
+    /// A timeout error occurs after `timeout` millionseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use uiautomation::asyncs::AsyncAutomation;
+    ///
+    /// async fn wait_enabled(automation: &AsyncAutomation) -> uiautomation::Result<()> {
+    ///     let button = automation.find_first_async(|a| a.create_matcher().name("OK")).await?;
+    ///     automation.wait_until(5000, 100, move |_| button.is_enabled()).await
+    /// }
+    /// ```
+    pub fn wait_until<F>(&self, timeout: u64, interval: u64, predicate: F) -> UITask<()> where F: FnMut(&UIAutomation) -> Result<bool> + Send + 'static {
+        self.schedule(move |_| {
+            let mut predicate = predicate;
+            let start = Instant::now();
+            move |automation: &UIAutomation| {
+                match predicate(automation) {
+                    Ok(true) => ControlFlow::Break(Ok(())),
+                    Ok(false) if start.elapsed() >= Duration::from_millis(timeout) => ControlFlow::Break(Err(Error::new(ERR_TIMEOUT, "wait time out"))),
+                    Ok(false) => ControlFlow::Continue(Duration::from_millis(interval)),
+                    Err(e) => ControlFlow::Break(Err(e))
+                }
+            }
+        })
+    }
+
+    fn find_attempt(matcher: UIMatcher, wanted: Option<usize>) -> impl FnMut(&UIAutomation) -> ControlFlow<Result<Vec<UIElement>>, Duration> {
+        let start = Instant::now();
+        let mut pids = None;
+        move |_| {
+            if pids.is_none() {
+                match matcher.get_process_ids() {
+                    Ok(ids) => pids = Some(ids),
+                    Err(e) => return ControlFlow::Break(Err(e))
+                }
+            }
+
+            let elements = match matcher.search_once(wanted, pids.as_ref().and_then(|ids| ids.as_deref())) {
+                Ok(elements) => elements,
+                Err(e) => return ControlFlow::Break(Err(e))
+            };
+
+            if elements.len() >= wanted.unwrap_or(1) {
+                ControlFlow::Break(Ok(elements))
+            } else if matcher.get_timeout() == 0 {
+                ControlFlow::Break(Err(Error::new(ERR_NOTFOUND, "can not find element")))
+            } else if start.elapsed() >= Duration::from_millis(matcher.get_timeout()) {
+                ControlFlow::Break(Err(Error::new(ERR_TIMEOUT, "find element time out")))
+            } else {
+                ControlFlow::Continue(Duration::from_millis(matcher.get_interval()))
+            }
+        }
+    }
+
+    fn schedule<R, S, A>(&self, setup: S) -> UITask<R>
+    where
+        R: Send + 'static,
+        S: FnOnce(&UIAutomation) -> A + Send + 'static,
+        A: FnMut(&UIAutomation) -> ControlFlow<Result<R>, Duration> + 'static
+    {
+        let task = UITask::new();
+        let state = task.state.clone();
+        let job: Job = Box::new(move |automation| {
+            let mut attempt = setup(automation);
+            Box::new(move |automation| {
+                if TaskState::is_cancelled(&state) {
+                    return None;
+                }
+
+                match attempt(automation) {
+                    ControlFlow::Continue(delay) => Some(delay),
+                    ControlFlow::Break(result) => {
+                        TaskState::complete(&state, result);
+                        None
+                    }
+                }
+            })
+        });
+
+        if self.sender.send(job).is_err() {
+            TaskState::complete(&task.state, Err(Error::new(ERR_INVALID_OBJECT, "the automation thread is stopped")));
+        }
+
+        task
+    }
+
+    fn run_loop(automation: &UIAutomation, receiver: Receiver<Job>) {
+        let mut attempts: Vec<(Instant, Attempt)> = Vec::new();
+        let mut connected = true;
+
+        loop {
+            let next = attempts.iter().map(|(due, _)| *due).min();
+            let job = match (connected, next) {
+                (false, None) => break,
+                (false, Some(due)) => {
+                    thread::sleep(due.saturating_duration_since(Instant::now()));
+                    None
+                },
+                (true, None) => receiver.recv().ok(),
+                (true, Some(due)) => match receiver.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                    Ok(job) => Some(job),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        connected = false;
+                        None
+                    }
+                }
+            };
+
+            match job {
+                Some(job) => attempts.push((Instant::now(), job(automation))),
+                None if connected && next.is_none() => connected = false,
+                None => {}
+            }
+
+            let now = Instant::now();
+            let mut pending = Vec::with_capacity(attempts.len());
+            for (due, mut attempt) in attempts.drain(..) {
+                if due > now {
+                    pending.push((due, attempt));
+                } else if let Some(delay) = attempt(automation) {
+                    pending.push((Instant::now() + delay, attempt));
+                }
+            }
+            attempts = pending;
+        }
+    }
+}
+
+impl Debug for AsyncAutomation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncAutomation").finish()
+    }
+}
+
+struct TaskState<R> {
+    result: Option<Result<R>>,
+    waker: Option<Waker>,
+    cancelled: bool
+}
+
+impl<R> TaskState<R> {
+    fn is_cancelled(state: &Mutex<Self>) -> bool {
+        state.lock().map(|s| s.cancelled).unwrap_or(true)
+    }
+
+    fn complete(state: &Mutex<Self>, result: Result<R>) {
+        let waker = if let Ok(mut state) = state.lock() {
+            state.result = Some(result);
+            state.waker.take()
+        } else {
+            None
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// A future of a task running on the apartment thread of `AsyncAutomation`.
+///
+/// Dropping the future cancels the task before its next retry.
+pub struct UITask<R> {
+    state: Arc<Mutex<TaskState<R>>>
+}
+
+impl<R> UITask<R> {
+    fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TaskState {
+                result: None,
+                waker: None,
+                cancelled: false
+            }))
+        }
+    }
+}
+
+impl<R> Future for UITask<R> {
+    type Output = Result<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Poll::Ready(Err(Error::new(ERR_INVALID_OBJECT, "the task is poisoned")))
+        };
+
+        if let Some(result) = state.result.take() {
+            Poll::Ready(result)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<R> Drop for UITask<R> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.cancelled = true;
+        }
+    }
+}
+
+impl<R> Debug for UITask<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UITask").finish()
+    }
+}
+
+/// A `UIElement` found by `AsyncAutomation`, which can be sent to other threads.
+///
+/// The element is created in the multithreaded apartment kept alive by `AsyncAutomation`,
+/// so it can be used on threads which have not initialized COM (such as async executor threads) or have joined the multithreaded apartment.
+///
+/// The apartment only lives as long as the `AsyncAutomation` thread, unless other threads have joined it.
+/// The thread stops after all clones of `AsyncAutomation` and all pending tasks are dropped, so keep one of them while using the element.
+#[derive(Debug, Clone)]
+pub struct SendElement(UIElement);
+
+unsafe impl Send for SendElement {}
+
+impl SendElement {
+    /// Retrieves the inner element.
+    pub fn into_inner(self) -> UIElement {
+        self.0
+    }
+}
+
+impl Deref for SendElement {
+    type Target = UIElement;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<UIElement> for SendElement {
+    fn as_ref(&self) -> &UIElement {
+        &self.0
+    }
+}
+
+impl From<SendElement> for UIElement {
+    fn from(value: SendElement) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    use crate::types::ControlType;
+
+    use super::AsyncAutomation;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park()
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_first_async() {
+        let automation = AsyncAutomation::new().unwrap();
+        let pane = block_on(automation.find_first_async(|a| a.create_matcher().depth(2).control_type(ControlType::Pane)));
+        assert!(pane.is_ok());
+        println!("{}", pane.unwrap().get_name().unwrap());
+
+        let nothing = block_on(automation.find_all_async(|a| a.create_matcher().timeout(300).name("You can find nothing!")));
+        assert!(nothing.is_err());
+    }
+
+    #[test]
+    fn test_cancel_async() {
+        let automation = AsyncAutomation::new().unwrap();
+        let task = automation.find_first_async(|a| a.create_matcher().timeout(60000).name("You can find nothing!"));
+        drop(task);
+
+        let root = block_on(automation.run(|a| a.get_root_element()?.get_name()));
+        assert!(root.is_ok());
+    }
+}
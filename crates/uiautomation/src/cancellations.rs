@@ -0,0 +1,186 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+#[cfg(feature = "process")]
+use windows::Win32::Foundation::CloseHandle;
+#[cfg(feature = "process")]
+use windows::Win32::Foundation::HANDLE;
+#[cfg(feature = "process")]
+use windows::Win32::Foundation::WAIT_TIMEOUT;
+#[cfg(feature = "process")]
+use windows::Win32::System::Threading::OpenProcess;
+#[cfg(feature = "process")]
+use windows::Win32::System::Threading::PROCESS_SYNCHRONIZE;
+#[cfg(feature = "process")]
+use windows::Win32::System::Threading::WaitForSingleObject;
+
+use super::errors::ERR_CANCELLED;
+use super::errors::Error;
+use super::errors::Result;
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: Mutex<bool>,
+    condvar: Condvar
+}
+
+/// A cloneable token used to abort long-running operations, such as `UIMatcher` searches and keyboard or mouse simulations.
+///
+/// All the clones share the same state, so cancelling any of them cancels the operations watching the token.
+/// A cancelled operation returns an error with code `ERR_CANCELLED`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::UIAutomation;
+/// use uiautomation::cancellations::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let stop = token.clone();
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_secs(1));
+///     stop.cancel();
+/// });
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let result = automation.create_matcher().name("You can find nothing!").timeout(60000).cancellation(token).find_first();
+/// assert!(result.is_err());
+/// ```
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<CancellationState>
+}
+
+impl CancellationToken {
+    /// Creates a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all the operations watching this token.
+    pub fn cancel(&self) {
+        if let Ok(mut cancelled) = self.state.cancelled.lock() {
+            *cancelled = true;
+        }
+        self.state.condvar.notify_all();
+    }
+
+    /// Checks whether the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.lock().map(|cancelled| *cancelled).unwrap_or(true)
+    }
+
+    /// Returns a cancelled error if the token is cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Error::new(ERR_CANCELLED, "operation is cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sleeps for `duration`, and wakes up as soon as the token is cancelled.
+    ///
+    /// Returns a cancelled error if the token is cancelled.
+    pub fn sleep(&self, duration: Duration) -> Result<()> {
+        let deadline = Instant::now() + duration;
+        let mut cancelled = self.state.cancelled.lock().map_err(|e| Error::from(e.to_string()))?;
+        while !*cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            cancelled = self.state.condvar.wait_timeout(cancelled, deadline - now).map_err(|e| Error::from(e.to_string()))?.0;
+        }
+        drop(cancelled);
+
+        self.check()
+    }
+
+    /// Cancels the token automatically when the process `pid` exits.
+    ///
+    /// The process is watched by a background thread, which stops when the token is cancelled or all its clones are dropped.
+    #[cfg(feature = "process")]
+    pub fn cancel_on_process_exit(&self, pid: u32) -> Result<()> {
+        let handle = unsafe {
+            OpenProcess(PROCESS_SYNCHRONIZE, false, pid)?
+        };
+        let raw_handle = handle.0 as usize;
+        let state = Arc::downgrade(&self.state);
+
+        std::thread::spawn(move || {
+            let handle = HANDLE(raw_handle as _);
+            loop {
+                let ret = unsafe { WaitForSingleObject(handle, 200) };
+                let Some(state) = state.upgrade() else {
+                    break;
+                };
+                let token = CancellationToken { state };
+                if ret != WAIT_TIMEOUT {
+                    token.cancel();
+                    break;
+                } else if token.is_cancelled() {
+                    break;
+                }
+            }
+
+            let _ = unsafe { CloseHandle(handle) };
+        });
+
+        Ok(())
+    }
+}
+
+impl Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken").field("cancelled", &self.is_cancelled()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use crate::errors::ERR_CANCELLED;
+
+    use super::CancellationToken;
+
+    #[test]
+    fn test_cancel_sleep() {
+        let token = CancellationToken::new();
+        assert!(token.sleep(Duration::from_millis(10)).is_ok());
+
+        let stop = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            stop.cancel();
+        });
+
+        let start = Instant::now();
+        let err = token.sleep(Duration::from_secs(10)).unwrap_err();
+        assert_eq!(err.code(), ERR_CANCELLED);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(token.check().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "process")]
+    fn test_cancel_on_process_exit() {
+        let mut child = std::process::Command::new("cmd.exe").args(["/C", "ping -n 2 127.0.0.1 > nul"]).spawn().unwrap();
+        let token = CancellationToken::new();
+        token.cancel_on_process_exit(child.id()).unwrap();
+        assert!(!token.is_cancelled());
+
+        child.wait().unwrap();
+        let start = Instant::now();
+        let err = token.sleep(Duration::from_secs(10)).unwrap_err();
+        assert_eq!(err.code(), ERR_CANCELLED);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
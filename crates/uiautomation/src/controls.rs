@@ -9,6 +9,29 @@ use super::UIElement;
 use super::errors::ERR_TYPE;
 use super::patterns::*;
 
+#[cfg(feature = "event")]
+use super::UIAutomation;
+#[cfg(feature = "event")]
+use super::events::EventRegistration;
+#[cfg(feature = "event")]
+use super::events::EventSpec;
+#[cfg(feature = "event")]
+use super::events::UIEvent;
+#[cfg(feature = "event")]
+use super::events::UIEventType;
+#[cfg(feature = "event")]
+use super::types::ExpandCollapseState;
+#[cfg(feature = "event")]
+use super::types::ToggleState;
+#[cfg(feature = "event")]
+use super::types::TreeScope;
+#[cfg(feature = "event")]
+use super::types::UIProperty;
+#[cfg(feature = "event")]
+use super::Error;
+#[cfg(feature = "event")]
+use super::variants;
+
 pub type ControlType = super::types::ControlType;
 
 // /// Defines enum for `windows::Win32::UI::Accessibility::UIA_CONTROLTYPE_ID`.
@@ -145,6 +168,16 @@ impl Control for ButtonControl {
     const TYPE: ControlType = ControlType::Button;
 }
 
+#[cfg(feature = "event")]
+impl ButtonControl {
+    /// Registers `handler` for the `Invoke_Invoked` events of the button.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    pub fn on_invoked<F>(&self, automation: &UIAutomation, handler: F) -> Result<EventRegistration> where F: Fn() -> Result<()> + 'static {
+        on_event(automation, &self.control, UIEventType::Invoke_Invoked, handler)
+    }
+}
+
 /// Wrapper a Calendar element as control.
 /// 
 /// + Must support: `Grid`, `Table`
@@ -171,6 +204,18 @@ impl Control for CheckBoxControl {
     const TYPE: ControlType = ControlType::CheckBox;
 }
 
+#[cfg(feature = "event")]
+impl CheckBoxControl {
+    /// Registers `handler` for the changes of the toggle state, which receives the new state.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    pub fn on_toggle_changed<F>(&self, automation: &UIAutomation, handler: F) -> Result<EventRegistration> where F: Fn(ToggleState) -> Result<()> + 'static {
+        on_property_changed(automation, &self.control, TreeScope::Element, UIProperty::ToggleToggleState, move |_, value| {
+            handler(to_state(value)?)
+        })
+    }
+}
+
 /// Wrapper a ComboBox element as control. The control type of the element must be `UIA_ComboBoxControlTypeId`.
 /// 
 /// + Must support: `ExpandCollapse`
@@ -184,6 +229,18 @@ impl Control for ComboBoxControl {
     const TYPE: ControlType = ControlType::ComboBox;
 }
 
+#[cfg(feature = "event")]
+impl ComboBoxControl {
+    /// Registers `handler` for the changes of the expand collapse state, which receives the new state.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    pub fn on_expand_state_changed<F>(&self, automation: &UIAutomation, handler: F) -> Result<EventRegistration> where F: Fn(ExpandCollapseState) -> Result<()> + 'static {
+        on_property_changed(automation, &self.control, TreeScope::Element, UIProperty::ExpandCollapseExpandCollapseState, move |_, value| {
+            handler(to_state(value)?)
+        })
+    }
+}
+
 /// Wrapper a DataGrid element as control. The control type of the element must be `UIA_DataGridControlTypeId`.
 /// 
 /// + Must support: `Grid`
@@ -236,6 +293,21 @@ impl Control for EditControl {
     const TYPE: ControlType = ControlType::Edit;
 }
 
+#[cfg(feature = "event")]
+impl EditControl {
+    /// Registers `handler` for the changes of the value, which receives the new value.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    pub fn on_value_changed<F>(&self, automation: &UIAutomation, handler: F) -> Result<EventRegistration> where F: Fn(String) -> Result<()> + 'static {
+        on_property_changed(automation, &self.control, TreeScope::Element, UIProperty::ValueValue, move |_, value| {
+            match value {
+                variants::Value::STRING(value) => handler(value),
+                _ => Err(Error::new(ERR_TYPE, "expect a string value"))
+            }
+        })
+    }
+}
+
 /// Wrapper a Group element as control. The control type of the element must be `UIA_GroupControlTypeId`.
 /// 
 /// + Must support: None
@@ -314,6 +386,25 @@ impl Control for ListControl {
     const TYPE: ControlType = ControlType::List;
 }
 
+#[cfg(feature = "event")]
+impl ListControl {
+    /// Registers `handler` for the selection changes of the list items.
+    ///
+    /// The handler receives the item and whether it is selected now, so it is called once for each item which is selected or unselected.
+    /// Only the changes of `SelectionItemIsSelected` are listened for. The lists whose items only raise the `SelectionItem_ElementSelected`,
+    /// `SelectionItem_ElementAddedToSelection` and `SelectionItem_ElementRemovedFromSelection` events are not reported,
+    /// and those events can be registered on `TreeScope::Descendants` by `UIAutomation::add_event_handler()` instead.
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    pub fn on_selection_changed<F>(&self, automation: &UIAutomation, handler: F) -> Result<EventRegistration> where F: Fn(&UIElement, bool) -> Result<()> + 'static {
+        on_property_changed(automation, &self.control, TreeScope::Descendants, UIProperty::SelectionItemIsSelected, move |item, value| {
+            match value {
+                variants::Value::BOOL(selected) => handler(item, selected),
+                _ => Err(Error::new(ERR_TYPE, "expect a bool value"))
+            }
+        })
+    }
+}
+
 /// Wrapper a ListItem element as control. The control type of the element must be `UIA_ListItemControlTypeId`.
 /// 
 /// + Must support: `SelectionItem`
@@ -649,3 +740,91 @@ impl WindowControl {
 impl Control for WindowControl {
     const TYPE: ControlType = ControlType::Window;
 }
+
+#[cfg(feature = "event")]
+impl WindowControl {
+    /// Registers `handler` for the `Window_WindowClosed` event of the window.
+    ///
+    /// The closed window can not raise events itself, so the event is listened for on the desktop and matched by the runtime id.
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    pub fn on_closed<F>(&self, automation: &UIAutomation, handler: F) -> Result<EventRegistration> where F: Fn() -> Result<()> + 'static {
+        let runtime_id = self.control.get_runtime_id()?;
+        let root = automation.get_root_element()?;
+        automation.add_event_handler(&EventSpec::automation(UIEventType::Window_WindowClosed, &root, TreeScope::Subtree), move |event| {
+            // the runtime id of the closed window is always cached in the sender.
+            match event.get_sender().get_runtime_id() {
+                Ok(id) if id == runtime_id => handler(),
+                _ => Ok(())
+            }
+        })
+    }
+}
+
+/// Registers `handler` for the events of `event_type` raised by `element` itself.
+#[cfg(feature = "event")]
+fn on_event<F>(automation: &UIAutomation, element: &UIElement, event_type: UIEventType, handler: F) -> Result<EventRegistration> where F: Fn() -> Result<()> + 'static {
+    automation.add_event_handler(&EventSpec::automation(event_type, element, TreeScope::Element), move |_| handler())
+}
+
+/// Registers `handler` for the changes of `property`, which receives the sender and the new value.
+#[cfg(feature = "event")]
+fn on_property_changed<F>(automation: &UIAutomation, element: &UIElement, scope: TreeScope, property: UIProperty, handler: F) -> Result<EventRegistration> where F: Fn(&UIElement, variants::Value) -> Result<()> + 'static {
+    automation.add_event_handler(&EventSpec::property_changed(element, scope, &[property]), move |event| {
+        match event {
+            UIEvent::PropertyChanged { sender, value, .. } => handler(sender, value.as_ref().clone()),
+            _ => Ok(())
+        }
+    })
+}
+
+/// Converts the `i32` value of a state property, such as `ToggleState` or `ExpandCollapseState`.
+#[cfg(feature = "event")]
+fn to_state<T>(value: variants::Value) -> Result<T> where T: TryFrom<i32, Error = Error> {
+    match value {
+        variants::Value::I4(state) | variants::Value::INT(state) => state.try_into(),
+        _ => Err(Error::new(ERR_TYPE, "expect an i32 value"))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "event")]
+mod tests {
+    use crate::types::ExpandCollapseState;
+    use crate::types::ToggleState;
+    use crate::variants::Value;
+    use crate::UIAutomation;
+
+    use super::ButtonControl;
+    use super::CheckBoxControl;
+    use super::ComboBoxControl;
+    use super::EditControl;
+    use super::ListControl;
+    use super::WindowControl;
+    use super::to_state;
+
+    #[test]
+    fn test_to_state() {
+        assert_eq!(to_state::<ToggleState>(Value::I4(0)).unwrap(), ToggleState::Off);
+        assert_eq!(to_state::<ToggleState>(Value::I4(1)).unwrap(), ToggleState::On);
+        assert_eq!(to_state::<ToggleState>(Value::I4(2)).unwrap(), ToggleState::Indeterminate);
+        assert!(to_state::<ToggleState>(Value::I4(3)).is_err());
+
+        assert_eq!(to_state::<ExpandCollapseState>(Value::I4(0)).unwrap(), ExpandCollapseState::Collapsed);
+        assert_eq!(to_state::<ExpandCollapseState>(Value::INT(3)).unwrap(), ExpandCollapseState::LeafNode);
+        assert!(to_state::<ExpandCollapseState>(Value::I4(-1)).is_err());
+        assert!(to_state::<ToggleState>(Value::STRING(String::from("On"))).is_err());
+    }
+
+    #[test]
+    fn test_event_helpers() {
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+
+        ButtonControl { control: root.clone() }.on_invoked(&automation, || Ok(())).unwrap().remove().unwrap();
+        CheckBoxControl { control: root.clone() }.on_toggle_changed(&automation, |_| Ok(())).unwrap().remove().unwrap();
+        ComboBoxControl { control: root.clone() }.on_expand_state_changed(&automation, |_| Ok(())).unwrap().remove().unwrap();
+        EditControl { control: root.clone() }.on_value_changed(&automation, |_| Ok(())).unwrap().remove().unwrap();
+        ListControl { control: root.clone() }.on_selection_changed(&automation, |_, _| Ok(())).unwrap().remove().unwrap();
+        WindowControl { control: root.clone() }.on_closed(&automation, || Ok(())).unwrap().remove().unwrap();
+    }
+}
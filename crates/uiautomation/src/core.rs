@@ -1,5 +1,9 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::fmt::Display;
+use std::hash::Hash;
+use std::str::FromStr;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -11,6 +15,14 @@ use windows::Win32::System::Com::CoCreateInstance;
 use windows::Win32::System::Com::CoInitializeEx;
 use windows::Win32::UI::Accessibility::CUIAutomation;
 use windows::Win32::UI::Accessibility::IUIAutomation;
+#[cfg(feature = "event")]
+use windows::Win32::UI::Accessibility::IUIAutomation3;
+#[cfg(feature = "event")]
+use windows::Win32::UI::Accessibility::IUIAutomation4;
+#[cfg(feature = "event")]
+use windows::Win32::UI::Accessibility::IUIAutomation5;
+#[cfg(feature = "event")]
+use windows::Win32::UI::Accessibility::IUIAutomation6;
 use windows::Win32::UI::Accessibility::IUIAutomationAndCondition;
 use windows::Win32::UI::Accessibility::IUIAutomationBoolCondition;
 use windows::Win32::UI::Accessibility::IUIAutomationCacheRequest;
@@ -24,6 +36,7 @@ use windows::Win32::UI::Accessibility::IUIAutomationNotCondition;
 use windows::Win32::UI::Accessibility::IUIAutomationOrCondition;
 use windows::Win32::UI::Accessibility::IUIAutomationPropertyCondition;
 use windows::Win32::UI::Accessibility::IUIAutomationTreeWalker;
+use windows::Win32::UI::Accessibility::UIA_CONTROLTYPE_ID;
 use windows::core::IUnknown;
 use windows::core::Interface;
 // use windows::Win32::UI::Accessibility::UIA_PROPERTY_ID;
@@ -34,6 +47,7 @@ use windows::core::Interface;
 // use crate::events::UIFocusChangedEventHandler;
 // use crate::events::UIPropertyChangedEventHandler;
 // use crate::events::UIStructureChangeEventHandler;
+use crate::cancellations::CancellationToken;
 use crate::filters::FnFilter;
 use crate::inputs::MouseButton;
 use crate::log_debug;
@@ -50,15 +64,27 @@ use crate::variants::SafeArray;
 use super::filters::ClassNameFilter;
 use super::filters::MatcherFilter;
 use super::filters::ControlTypeFilter;
+use super::filters::FuzzyNameFilter;
+use super::filters::LabeledFilter;
 use super::filters::NameFilter;
+use super::filters::SpatialFilter;
+use super::filters::SpatialRelation;
+use super::errors::ERR_FORMAT;
+use super::errors::ERR_NONE;
 use super::errors::ERR_NOTFOUND;
 use super::errors::ERR_TIMEOUT;
+#[cfg(feature = "event")]
+use super::errors::ERR_TYPE;
 use super::errors::Error;
 use super::errors::Result;
 // use super::patterns::UIPattern;
+use super::locales::LocaleAliases;
+use super::texts::NameNormalizer;
+use super::texts::Similarity;
 use super::types::Handle;
 use super::types::Rect;
 use super::types::Point;
+use super::variants::Value;
 use super::variants::Variant;
 
 // #[cfg(feature = "input")]
@@ -69,6 +95,8 @@ use crate::inputs::Mouse;
 use super::inputs::Keyboard;
 #[cfg(feature = "event")]
 use super::events::*;
+#[cfg(feature = "event")]
+use super::types::TextEditChangeType;
 #[cfg(feature = "clipboard")]
 use super::clipboards::Clipboard;
 
@@ -127,6 +155,52 @@ impl UIAutomation {
         Ok(UIElement::from(element))
     }
 
+    /// Resolves the element referred by `reference`.
+    ///
+    /// The element is searched by its runtime id beneath the window of the reference.
+    /// If the runtime id is not found, for example the element is recreated, it is searched by the locator of the reference.
+    pub fn resolve(&self, reference: &ElementRef) -> Result<UIElement> {
+        let root = if reference.window_handle != 0 {
+            self.element_from_handle(Handle::from(reference.window_handle)).or_else(|_| self.get_root_element())?
+        } else {
+            self.get_root_element()?
+        };
+
+        if !reference.runtime_id.is_empty() {
+            if root.get_runtime_id().is_ok_and(|id| id == reference.runtime_id) {
+                return Ok(root);
+            }
+
+            let condition = self.create_property_condition(UIProperty::RuntimeId, Variant::from(Value::ArrayI4(reference.runtime_id.clone())), None)?;
+            if let Ok(element) = root.find_first(TreeScope::Descendants, &condition) {
+                return Ok(element);
+            }
+        }
+
+        let mut condition = self.create_true_condition()?;
+        if reference.process_id != 0 {
+            let pid_condition = self.create_property_condition(UIProperty::ProcessId, Variant::from(reference.process_id as i32), None)?;
+            condition = self.create_and_condition(condition, pid_condition)?;
+        }
+        if let Some(control_type) = reference.control_type {
+            let type_condition = self.create_property_condition(UIProperty::ControlType, Variant::from(control_type as i32), None)?;
+            condition = self.create_and_condition(condition, type_condition)?;
+        }
+        let properties = [
+            (UIProperty::AutomationId, &reference.automation_id),
+            (UIProperty::ClassName, &reference.classname),
+            (UIProperty::Name, &reference.name)
+        ];
+        for (property, value) in properties {
+            if !value.is_empty() {
+                let property_condition = self.create_property_condition(property, Variant::from(value), None)?;
+                condition = self.create_and_condition(condition, property_condition)?;
+            }
+        }
+
+        root.find_first(TreeScope::Subtree, &condition).map_err(|_| Error::new(ERR_NOTFOUND, "can not resolve element"))
+    }
+
     /// Retrieves a UI Automation element for the specified window, prefetches the requested properties and control patterns, and stores the prefetched items in the cache.
     pub fn element_from_handle_build_cache(&self, hwnd: Handle, cache_request: &UICacheRequest) -> Result<UIElement> {
         let element = unsafe {
@@ -333,13 +407,15 @@ impl UIAutomation {
     }
 
     /// Registers a method that handles Microsoft UI Automation events.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
     #[cfg(feature = "event")]
-    pub fn add_automation_event_handler(&self, event_type: UIEventType, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UIEventHandler) -> Result<()> {
+    pub fn add_automation_event_handler(&self, event_type: UIEventType, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UIEventHandler) -> Result<EventRegistration> {
         let cache_request = cache_request.map(|r| r.as_ref());
         unsafe {
             self.automation.AddAutomationEventHandler(event_type.into(), element, scope.into(), cache_request, handler)?
         };
-        Ok(())
+        EventRegistration::new(self, Registration::Automation(event_type, element.clone(), handler.clone()))
     }
 
     /// Removes the specified UI Automation event handler.
@@ -352,14 +428,16 @@ impl UIAutomation {
     }
 
     /// Registers a method that handles and array of property-changed events.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
     #[cfg(feature = "event")]
-    pub fn add_property_changed_event_handler(&self, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UIPropertyChangedEventHandler, properties: &[UIProperty]) -> Result<()> {
+    pub fn add_property_changed_event_handler(&self, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UIPropertyChangedEventHandler, properties: &[UIProperty]) -> Result<EventRegistration> {
         let cache_request = cache_request.map(|r| r.as_ref());
         let prop_arr: Vec<windows::Win32::UI::Accessibility::UIA_PROPERTY_ID> = properties.iter().map(|p| (*p).into()).collect();
         unsafe {
             self.automation.AddPropertyChangedEventHandlerNativeArray(element, scope.into(), cache_request, handler, &prop_arr)?
         };
-        Ok(())
+        EventRegistration::new(self, Registration::PropertyChanged(element.clone(), handler.clone()))
     }
 
     /// Removes a property-changed event handler.
@@ -372,13 +450,15 @@ impl UIAutomation {
     }
 
     /// Registers a method that handles structure-changed events.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
     #[cfg(feature = "event")]
-    pub fn add_structure_changed_event_handler(&self, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UIStructureChangeEventHandler) -> Result<()> {
+    pub fn add_structure_changed_event_handler(&self, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UIStructureChangeEventHandler) -> Result<EventRegistration> {
         let cache_request = cache_request.map(|r| r.as_ref());
         unsafe {
             self.automation.AddStructureChangedEventHandler(element, scope.into(), cache_request, handler)?
         };
-        Ok(())
+        EventRegistration::new(self, Registration::StructureChanged(element.clone(), handler.clone()))
     }
 
     /// Removes a structure-changed event handler.
@@ -391,13 +471,15 @@ impl UIAutomation {
     }
 
     /// Registers a method that handles focus-changed events.
+    ///
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
     #[cfg(feature = "event")]
-    pub fn add_focus_changed_event_handler(&self, cache_request: Option<&UICacheRequest>, handler: &UIFocusChangedEventHandler) -> Result<()> {
+    pub fn add_focus_changed_event_handler(&self, cache_request: Option<&UICacheRequest>, handler: &UIFocusChangedEventHandler) -> Result<EventRegistration> {
         let cache_request = cache_request.map(|r| r.as_ref());
         unsafe {
             self.automation.AddFocusChangedEventHandler(cache_request, handler)?
         };
-        Ok(())
+        EventRegistration::new(self, Registration::FocusChanged(handler.clone()))
     }
 
     /// Removes a focus-changed event handler.
@@ -409,6 +491,273 @@ impl UIAutomation {
         Ok(())
     }
 
+    /// Registers a method that handles notification events.
+    ///
+    /// It requires `IUIAutomation5`, which is available from Windows 10 version 1709.
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    #[cfg(feature = "event")]
+    pub fn add_notification_event_handler(&self, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UINotificationEventHandler) -> Result<EventRegistration> {
+        let automation: IUIAutomation5 = self.automation.cast()?;
+        let cache_request = cache_request.map(|r| r.as_ref());
+        unsafe {
+            automation.AddNotificationEventHandler(element, scope.into(), cache_request, handler)?
+        };
+        EventRegistration::new(self, Registration::Notification(element.clone(), handler.clone()))
+    }
+
+    /// Removes a notification event handler.
+    #[cfg(feature = "event")]
+    pub fn remove_notification_event_handler(&self, element: &UIElement, handler: &UINotificationEventHandler) -> Result<()> {
+        let automation: IUIAutomation5 = self.automation.cast()?;
+        unsafe {
+            automation.RemoveNotificationEventHandler(element, handler)?
+        };
+        Ok(())
+    }
+
+    /// Registers a method that handles change events of `change_types`, such as annotation types or style ids.
+    ///
+    /// It requires `IUIAutomation4`, which is available from Windows 10 version 1607.
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    #[cfg(feature = "event")]
+    pub fn add_changes_event_handler(&self, element: &UIElement, scope: TreeScope, change_types: &[i32], cache_request: Option<&UICacheRequest>, handler: &UIChangesEventHandler) -> Result<EventRegistration> {
+        let automation: IUIAutomation4 = self.automation.cast()?;
+        let cache_request = cache_request.map(|r| r.as_ref());
+        unsafe {
+            automation.AddChangesEventHandler(element, scope.into(), change_types, cache_request, handler)?
+        };
+        EventRegistration::new(self, Registration::Changes(element.clone(), handler.clone()))
+    }
+
+    /// Removes a changes event handler.
+    #[cfg(feature = "event")]
+    pub fn remove_changes_event_handler(&self, element: &UIElement, handler: &UIChangesEventHandler) -> Result<()> {
+        let automation: IUIAutomation4 = self.automation.cast()?;
+        unsafe {
+            automation.RemoveChangesEventHandler(element, handler)?
+        };
+        Ok(())
+    }
+
+    /// Registers a method that handles programmatic text-edit events of `change_type`.
+    ///
+    /// It requires `IUIAutomation3`, which is available from Windows 8.1.
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    #[cfg(feature = "event")]
+    pub fn add_text_edit_text_changed_event_handler(&self, element: &UIElement, scope: TreeScope, change_type: TextEditChangeType, cache_request: Option<&UICacheRequest>, handler: &UITextEditTextChangedEventHandler) -> Result<EventRegistration> {
+        let automation: IUIAutomation3 = self.automation.cast()?;
+        let cache_request = cache_request.map(|r| r.as_ref());
+        unsafe {
+            automation.AddTextEditTextChangedEventHandler(element, scope.into(), change_type.into(), cache_request, handler)?
+        };
+        EventRegistration::new(self, Registration::TextEditTextChanged(element.clone(), handler.clone()))
+    }
+
+    /// Removes a text-edit event handler.
+    #[cfg(feature = "event")]
+    pub fn remove_text_edit_text_changed_event_handler(&self, element: &UIElement, handler: &UITextEditTextChangedEventHandler) -> Result<()> {
+        let automation: IUIAutomation3 = self.automation.cast()?;
+        unsafe {
+            automation.RemoveTextEditTextChangedEventHandler(element, handler)?
+        };
+        Ok(())
+    }
+
+    /// Registers a method that handles active-text-position-changed events.
+    ///
+    /// It requires `IUIAutomation6`, which is available from Windows 10 version 1809.
+    /// The handler is removed when the returned registration is dropped, unless it is detached.
+    #[cfg(feature = "event")]
+    pub fn add_active_text_position_changed_event_handler(&self, element: &UIElement, scope: TreeScope, cache_request: Option<&UICacheRequest>, handler: &UIActiveTextPositionChangedEventHandler) -> Result<EventRegistration> {
+        let automation: IUIAutomation6 = self.automation.cast()?;
+        let cache_request = cache_request.map(|r| r.as_ref());
+        unsafe {
+            automation.AddActiveTextPositionChangedEventHandler(element, scope.into(), cache_request, handler)?
+        };
+        EventRegistration::new(self, Registration::ActiveTextPositionChanged(element.clone(), handler.clone()))
+    }
+
+    /// Removes an active-text-position-changed event handler.
+    #[cfg(feature = "event")]
+    pub fn remove_active_text_position_changed_event_handler(&self, element: &UIElement, handler: &UIActiveTextPositionChangedEventHandler) -> Result<()> {
+        let automation: IUIAutomation6 = self.automation.cast()?;
+        unsafe {
+            automation.RemoveActiveTextPositionChangedEventHandler(element, handler)?
+        };
+        Ok(())
+    }
+
+    /// Registers `handler` for the events described by `spec`. All kinds of events are passed to the handler as `UIEvent`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use uiautomation::UIAutomation;
+    /// use uiautomation::events::EventSpec;
+    /// use uiautomation::events::UIEventType;
+    /// use uiautomation::types::TreeScope;
+    /// use uiautomation::types::UIProperty;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let root = automation.get_root_element().unwrap();
+    ///
+    /// let specs = [
+    ///     EventSpec::automation(UIEventType::Window_WindowOpened, &root, TreeScope::Subtree),
+    ///     EventSpec::property_changed(&root, TreeScope::Subtree, &[UIProperty::Name]),
+    ///     EventSpec::focus_changed()
+    /// ];
+    /// let registrations: Vec<_> = specs.iter().map(|spec| automation.add_event_handler(spec, |event| {
+    ///     println!("{} {:?}", event.get_timestamp(), event);
+    ///     Ok(())
+    /// }).unwrap()).collect();
+    /// ```
+    #[cfg(feature = "event")]
+    pub fn add_event_handler<F>(&self, spec: &EventSpec, handler: F) -> Result<EventRegistration> where F: Fn(&UIEvent) -> Result<()> + 'static {
+        EventRegistration::register(self, spec, Box::new(handler))
+    }
+
+    /// Subscribes the events described by `spec`, which can be received from the returned subscription on any thread.
+    #[cfg(feature = "event")]
+    pub fn subscribe_spec(&self, spec: &EventSpec) -> Result<Subscription> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // the receiver may be dropped before the handler is removed, so the send errors are ignored.
+        let registration = self.add_event_handler(spec, move |event| {
+            let _ = sender.send(event.clone());
+            Ok(())
+        })?;
+
+        Ok(Subscription::new(registration, receiver))
+    }
+
+    /// Subscribes the Microsoft UI Automation events of `event_type`, which can be received from the returned subscription on any thread.
+    #[cfg(feature = "event")]
+    pub fn subscribe(&self, event_type: UIEventType, element: &UIElement, scope: TreeScope) -> Result<Subscription> {
+        self.subscribe_spec(&EventSpec::automation(event_type, element, scope))
+    }
+
+    /// Subscribes the property-changed events of `properties`, which can be received from the returned subscription on any thread.
+    #[cfg(feature = "event")]
+    pub fn subscribe_property_changed(&self, element: &UIElement, scope: TreeScope, properties: &[UIProperty]) -> Result<Subscription> {
+        self.subscribe_spec(&EventSpec::property_changed(element, scope, properties))
+    }
+
+    /// Subscribes the structure-changed events, which can be received from the returned subscription on any thread.
+    #[cfg(feature = "event")]
+    pub fn subscribe_structure_changed(&self, element: &UIElement, scope: TreeScope) -> Result<Subscription> {
+        self.subscribe_spec(&EventSpec::structure_changed(element, scope))
+    }
+
+    /// Subscribes the focus-changed events, which can be received from the returned subscription on any thread.
+    #[cfg(feature = "event")]
+    pub fn subscribe_focus_changed(&self) -> Result<Subscription> {
+        self.subscribe_spec(&EventSpec::focus_changed())
+    }
+
+    /// Waits for an event described by `spec` which satisfies `predicate`, at most `timeout` millionseconds.
+    ///
+    /// The event handler is registered before running `action`, so the events raised by `action` are not missed.
+    /// The handler is removed before returning. The predicate and the action run on current thread.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use uiautomation::UIAutomation;
+    /// use uiautomation::events::EventSpec;
+    /// use uiautomation::events::UIEventType;
+    /// use uiautomation::types::TreeScope;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let root = automation.get_root_element().unwrap();
+    /// let save = automation.create_matcher().name("Save").find_first().unwrap();
+    ///
+    /// let spec = EventSpec::automation(UIEventType::Window_WindowOpened, &root, TreeScope::Subtree);
+    /// let event = automation.wait_for(&spec, |event| {
+    ///     event.get_sender().get_name().map(|name| name.contains("Save As")).unwrap_or(false)
+    /// }, Some(&|| save.click()), 5000).unwrap();
+    /// println!("{}", event.get_sender());
+    /// ```
+    #[cfg(feature = "event")]
+    pub fn wait_for<P>(&self, spec: &EventSpec, mut predicate: P, action: Option<&dyn Fn() -> Result<()>>, timeout: u64) -> Result<UIEvent> where P: FnMut(&UIEvent) -> bool {
+        let subscription = self.subscribe_spec(spec)?;
+        if let Some(action) = action {
+            action()?;
+        }
+
+        let deadline = Local::now().timestamp_millis() + timeout as i64;
+        loop {
+            let remaining = deadline - Local::now().timestamp_millis();
+            if remaining <= 0 {
+                return Err(Error::new(ERR_TIMEOUT, "wait event time out"));
+            }
+
+            match subscription.recv_timeout(Duration::from_millis(remaining as u64)) {
+                Ok(event) if predicate(&event) => return Ok(event),
+                Ok(_) => {},
+                Err(_) => return Err(Error::new(ERR_TIMEOUT, "wait event time out"))
+            }
+        }
+    }
+
+    /// Waits for an event of `event_type` whose sender satisfies `predicate`, at most `timeout` millionseconds.
+    ///
+    /// Returns the sender of the event.
+    #[cfg(feature = "event")]
+    pub fn wait_for_event<P>(&self, event_type: UIEventType, element: &UIElement, scope: TreeScope, mut predicate: P, timeout: u64) -> Result<UIElement> where P: FnMut(&UIElement) -> bool {
+        let spec = EventSpec::automation(event_type, element, scope);
+        let event = self.wait_for(&spec, |event| predicate(event.get_sender()), None, timeout)?;
+        Ok(event.get_sender().clone())
+    }
+
+    /// Runs `action` after the event handler is registered, then waits in the same way as `wait_for_event()`.
+    #[cfg(feature = "event")]
+    pub fn wait_for_event_after<P, A>(&self, event_type: UIEventType, element: &UIElement, scope: TreeScope, mut predicate: P, action: A, timeout: u64) -> Result<UIElement> where P: FnMut(&UIElement) -> bool, A: Fn() -> Result<()> {
+        let spec = EventSpec::automation(event_type, element, scope);
+        let event = self.wait_for(&spec, |event| predicate(event.get_sender()), Some(&action), timeout)?;
+        Ok(event.get_sender().clone())
+    }
+
+    /// Waits for a change of `property` whose sender and new value satisfy `predicate`, at most `timeout` millionseconds.
+    ///
+    /// Returns the new value of the property.
+    #[cfg(feature = "event")]
+    pub fn wait_for_property_changed<P>(&self, element: &UIElement, scope: TreeScope, property: UIProperty, predicate: P, timeout: u64) -> Result<Value> where P: FnMut(&UIElement, &Value) -> bool {
+        self.wait_property(element, scope, property, predicate, None, timeout)
+    }
+
+    /// Runs `action` after the event handler is registered, then waits in the same way as `wait_for_property_changed()`.
+    #[cfg(feature = "event")]
+    pub fn wait_for_property_changed_after<P, A>(&self, element: &UIElement, scope: TreeScope, property: UIProperty, predicate: P, action: A, timeout: u64) -> Result<Value> where P: FnMut(&UIElement, &Value) -> bool, A: Fn() -> Result<()> {
+        self.wait_property(element, scope, property, predicate, Some(&action), timeout)
+    }
+
+    #[cfg(feature = "event")]
+    fn wait_property<P>(&self, element: &UIElement, scope: TreeScope, property: UIProperty, mut predicate: P, action: Option<&dyn Fn() -> Result<()>>, timeout: u64) -> Result<Value> where P: FnMut(&UIElement, &Value) -> bool {
+        let spec = EventSpec::property_changed(element, scope, &[property]);
+        let event = self.wait_for(&spec, |event| match event {
+            UIEvent::PropertyChanged { sender, property: changed, value, .. } if *changed == property => {
+                predicate(sender, value)
+            },
+            _ => false
+        }, action, timeout)?;
+
+        match event {
+            UIEvent::PropertyChanged { value, .. } => Ok(value.into_inner()),
+            _ => Err(Error::new(ERR_TYPE, "unexpected event"))
+        }
+    }
+
+    /// Creates an asynchronous stream of the events described by `spec`, which buffers at most 256 events and drops the oldest ones when full.
+    #[cfg(all(feature = "event", feature = "async"))]
+    pub fn event_stream(&self, spec: &EventSpec) -> Result<EventStream> {
+        self.event_stream_with(spec, 256, OverflowPolicy::DropOldest)
+    }
+
+    /// Creates an asynchronous stream of the events described by `spec`, which buffers at most `capacity` events and handles the overflow by `policy`.
+    #[cfg(all(feature = "event", feature = "async"))]
+    pub fn event_stream_with(&self, spec: &EventSpec, capacity: usize, policy: OverflowPolicy) -> Result<EventStream> {
+        EventStream::new(self, spec, capacity, policy)
+    }
+
     /// Removes all registered Microsoft UI Automation event handlers.
     pub fn remove_all_event_handlers(&self) -> Result<()> {
         unsafe {
@@ -441,9 +790,12 @@ impl AsRef<IUIAutomation> for UIAutomation {
 /// A wrapper for windows `IUIAutomationElement` interface.
 ///
 /// Exposes methods and properties for a UI Automation element, which represents a UI item.
+///
+/// The elements are compared and hashed by the runtime ids retrieved when they are created, so they keep stable after the UI items are gone.
 #[derive(Clone)]
 pub struct UIElement {
-    element: IUIAutomationElement
+    element: IUIAutomationElement,
+    runtime_id: Option<Vec<i32>>
 }
 
 impl UIElement {
@@ -503,16 +855,175 @@ impl UIElement {
         Self::to_elements(elements)
     }
 
+    /// Retrieves the elements in `scope` with the specified `properties` and `patterns` cached in a single call,
+    /// and returns them as an owned tree which can be walked without further cross-process calls.
+    ///
+    /// The element itself is always cached as the root of the tree, and the control view is used as the tree filter.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    /// use uiautomation::patterns::UIPatternType;
+    /// use uiautomation::types::TreeScope;
+    /// use uiautomation::types::UIProperty;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let root = automation.get_root_element().unwrap();
+    /// let tree = root.build_cached_tree(&automation, TreeScope::Children, &[UIProperty::Name, UIProperty::ControlType], &[UIPatternType::Window]).unwrap();
+    /// for node in tree.iter() {
+    ///     println!("{}{}", " ".repeat(node.get_depth() as usize * 4), node.get_name().unwrap());
+    /// }
+    /// ```
+    #[cfg(feature = "pattern")]
+    pub fn build_cached_tree(&self, automation: &UIAutomation, scope: TreeScope, properties: &[UIProperty], patterns: &[super::patterns::UIPatternType]) -> Result<UICachedNode> {
+        let cache_request = automation.create_cache_request()?;
+        for property in properties {
+            cache_request.add_property(*property)?;
+        }
+        for pattern in patterns {
+            cache_request.add_pattern(*pattern)?;
+        }
+        unsafe {
+            cache_request.request.SetTreeScope(windows::Win32::UI::Accessibility::TreeScope(scope as i32 | TreeScope::Element as i32))?
+        };
+
+        self.build_cached_tree_with_request(&cache_request)
+    }
+
+    /// Retrieves the elements in the scope of `cache_request` in a single call, and returns them as an owned tree.
+    ///
+    /// The tree scope of `cache_request` decides how deep the tree is, and should include `TreeScope::Element` to cache the root.
+    pub fn build_cached_tree_with_request(&self, cache_request: &UICacheRequest) -> Result<UICachedNode> {
+        let scope = unsafe {
+            cache_request.request.TreeScope()?
+        };
+        let element = self.build_updated_cache(cache_request)?;
+        UICachedNode::build(element, 0, scope.0)
+    }
+
     /// Receives the runtime ID as a vec of integers.
     pub fn get_runtime_id(&self) -> Result<Vec<i32>> {
         let id = unsafe {
             self.element.GetRuntimeId()?
         };
 
-        let arr = SafeArray::new(id, false);
+        // the returned array is owned by the caller, so it is destroyed after converted.
+        let arr = SafeArray::new(id, true);
         arr.try_into()
     }
 
+    /// Retrieves the parent element in the control view.
+    pub fn parent(&self, automation: &UIAutomation) -> Result<UIElement> {
+        automation.get_control_view_walker()?.get_parent(self)
+    }
+
+    /// Retrieves all the ancestors in the control view, from the parent to the desktop.
+    pub fn ancestors(&self, automation: &UIAutomation) -> Result<Vec<UIElement>> {
+        let walker = automation.get_control_view_walker()?;
+        let mut ancestors = Vec::new();
+        let mut next = walker.get_parent(self);
+        while let Ok(parent) = next {
+            next = walker.get_parent(&parent);
+            ancestors.push(parent);
+        }
+
+        Ok(ancestors)
+    }
+
+    /// Retrieves the top-level window containing the element, which is the ancestor (or the element itself) whose parent is the desktop.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use uiautomation::UIAutomation;
+    /// use uiautomation::types::ControlType;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let edit = automation.create_matcher().control_type(ControlType::Edit).find_first().unwrap();
+    /// let window = edit.top_level_window(&automation).unwrap();
+    /// window.set_focus().unwrap();
+    /// ```
+    pub fn top_level_window(&self, automation: &UIAutomation) -> Result<UIElement> {
+        let walker = automation.get_control_view_walker()?;
+        let root = automation.get_root_element()?;
+
+        let mut current = self.clone();
+        loop {
+            let parent = walker.get_parent(&current).map_err(|_| Error::new(ERR_NOTFOUND, "can not find top-level window"))?;
+            if automation.compare_elements(&parent, &root)? {
+                return Ok(current);
+            }
+            current = parent;
+        }
+    }
+
+    /// Retrieves the element itself or its nearest ancestor which matches `filter`.
+    pub fn closest(&self, automation: &UIAutomation, filter: &dyn MatcherFilter) -> Result<UIElement> {
+        if filter.judge(self)? {
+            return Ok(self.clone());
+        }
+
+        let walker = automation.get_control_view_walker()?;
+        let mut next = walker.get_parent(self);
+        while let Ok(parent) = next {
+            if filter.judge(&parent)? {
+                return Ok(parent);
+            }
+            next = walker.get_parent(&parent);
+        }
+
+        Err(Error::new(ERR_NOTFOUND, "can not find matched ancestor"))
+    }
+
+    /// Retrieves the index (starting from `0`) of the element among the children of its parent in the control view.
+    pub fn index_in_parent(&self, automation: &UIAutomation) -> Result<usize> {
+        let walker = automation.get_control_view_walker()?;
+        let parent = walker.get_parent(self)?;
+
+        let mut index = 0;
+        let mut next = walker.get_first_child(&parent);
+        while let Ok(child) = next {
+            if automation.compare_elements(&child, self)? {
+                return Ok(index);
+            }
+            index += 1;
+            next = walker.get_next_sibling(&child);
+        }
+
+        Err(Error::new(ERR_NOTFOUND, "can not find element in parent"))
+    }
+
+    /// Retrieves the information of the process which owns the element.
+    #[cfg(feature = "process")]
+    pub fn process_info(&self) -> Result<super::processes::ProcessInfo> {
+        super::processes::ProcessInfo::from_id(self.get_process_id()?)
+    }
+
+    /// Creates a reference to the element, which can be stored as a string and resolved by `UIAutomation::resolve()` later.
+    pub fn to_ref(&self, automation: &UIAutomation) -> Result<ElementRef> {
+        let walker = automation.get_control_view_walker()?;
+        let mut window = self.clone();
+        let mut window_handle: isize = window.get_native_window_handle()?.into();
+        while window_handle == 0 {
+            let Ok(parent) = walker.get_parent(&window) else {
+                break;
+            };
+            window = parent;
+            window_handle = window.get_native_window_handle()?.into();
+        }
+
+        Ok(ElementRef {
+            runtime_id: self.get_runtime_id()?,
+            process_id: self.get_process_id()?,
+            window_handle,
+            control_type: self.get_control_type().ok(),
+            automation_id: self.get_automation_id().unwrap_or_default(),
+            classname: self.get_classname().unwrap_or_default(),
+            name: self.get_name().unwrap_or_default()
+        })
+    }
+
     /// Retrieves the name of the element.
     pub fn get_name(&self) -> Result<String> {
         let name = unsafe {
@@ -1362,8 +1873,15 @@ impl UIElement {
 
 impl From<IUIAutomationElement> for UIElement {
     fn from(element: IUIAutomationElement) -> Self {
+        // the runtime id is always cached, so it is available without a cross-process call.
+        // the returned array is owned by the caller, so it is destroyed after converted.
+        let runtime_id = unsafe {
+            element.GetRuntimeId()
+        }.ok().and_then(|id| SafeArray::new(id, true).try_into().ok());
+
         UIElement {
-            element
+            element,
+            runtime_id
         }
     }
 }
@@ -1407,6 +1925,24 @@ impl Display for UIElement {
     }
 }
 
+/// Compares the elements by their runtime ids, or by the underlying interfaces when the runtime ids are not available.
+impl PartialEq for UIElement {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.runtime_id, &other.runtime_id) {
+            (Some(id), Some(other_id)) => id == other_id,
+            _ => self.element == other.element
+        }
+    }
+}
+
+impl Eq for UIElement {}
+
+impl Hash for UIElement {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.runtime_id.as_deref().unwrap_or_default().hash(state);
+    }
+}
+
 impl Debug for UIElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut d = f.debug_struct("UIElement");
@@ -1533,29 +2069,139 @@ impl Param<IUIAutomationCacheRequest> for &UICacheRequest {
     }
 }
 
-/// A wrapper for windows `IUIAutomationTreeWalker` interface.
+/// A node of the element tree built by `UIElement::build_cached_tree()`.
 ///
-/// Exposes properties and methods that UI Automation client applications use to view and navigate the UI Automation elements on the desktop.
-#[derive(Clone)]
-pub struct UITreeWalker {
-    tree_walker: IUIAutomationTreeWalker
+/// Each node owns its cached children, so the whole tree can be walked without further cross-process calls.
+#[derive(Debug, Clone)]
+pub struct UICachedNode {
+    element: UIElement,
+    depth: u32,
+    children: Vec<UICachedNode>
 }
 
-impl UITreeWalker {
-    /// Retrieves the parent element of the specified UI Automation element.
-    pub fn get_parent(&self, element: &UIElement) -> Result<UIElement> {
-        let parent: IUIAutomationElement;
-        unsafe {
-            parent = self.tree_walker.GetParentElement(&element.element)?;
+impl UICachedNode {
+    fn build(element: UIElement, depth: u32, scope: i32) -> Result<Self> {
+        // the children are cached for the root with `TreeScope::Children`, and for all levels with `TreeScope::Descendants`.
+        let cached = if depth == 0 {
+            scope & (TreeScope::Children as i32 | TreeScope::Descendants as i32) != 0
+        } else {
+            scope & TreeScope::Descendants as i32 != 0
+        };
+
+        let mut children = Vec::new();
+        if cached {
+            // an empty array is returned as null, which is an error without code.
+            let elements = match element.get_cached_children() {
+                Ok(elements) => elements,
+                Err(e) if e.code() == ERR_NONE => Vec::new(),
+                Err(e) => return Err(e)
+            };
+            for child in elements {
+                children.push(Self::build(child, depth + 1, scope)?);
+            }
         }
 
-        Ok(UIElement::from(parent))
+        Ok(Self {
+            element,
+            depth,
+            children
+        })
     }
 
-    /// Retrieves the parent element of the specified UI Automation element, and caches properties and control patterns.
-    pub fn get_parent_build_cache(&self, element: &UIElement, cache_request: &UICacheRequest) -> Result<UIElement> {
-        let parent = unsafe {
-            self.tree_walker.GetParentElementBuildCache(element, cache_request)?
+    /// Retrieves the cached element of this node.
+    pub fn get_element(&self) -> &UIElement {
+        &self.element
+    }
+
+    /// Retrieves the depth of this node. The root node of the tree is at depth `0`.
+    pub fn get_depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Retrieves the child nodes.
+    pub fn get_children(&self) -> &[UICachedNode] {
+        &self.children
+    }
+
+    /// Walks the tree in depth-first order, starting from this node.
+    pub fn iter(&self) -> UICachedNodeIter<'_> {
+        UICachedNodeIter {
+            stack: vec![self]
+        }
+    }
+
+    /// Retrieves the cached name of the element.
+    pub fn get_name(&self) -> Result<String> {
+        self.element.get_cached_name()
+    }
+
+    /// Retrieves the cached control type of the element.
+    pub fn get_control_type(&self) -> Result<ControlType> {
+        self.element.get_cached_control_type()
+    }
+
+    /// Retrieves the cached value of a property for the element.
+    pub fn get_property_value(&self, property: UIProperty) -> Result<Variant> {
+        self.element.get_cached_property_value(property)
+    }
+
+    /// Retrieves the cached control pattern `<T>` of the element.
+    #[cfg(feature = "pattern")]
+    pub fn get_pattern<T: super::patterns::UIPattern + TryFrom<IUnknown, Error = Error>>(&self) -> Result<T> {
+        self.element.get_cached_pattern()
+    }
+}
+
+impl From<UICachedNode> for UIElement {
+    fn from(node: UICachedNode) -> Self {
+        node.element
+    }
+}
+
+impl AsRef<UIElement> for UICachedNode {
+    fn as_ref(&self) -> &UIElement {
+        &self.element
+    }
+}
+
+/// A depth-first iterator over the nodes of a `UICachedNode` tree.
+pub struct UICachedNodeIter<'a> {
+    stack: Vec<&'a UICachedNode>
+}
+
+impl<'a> Iterator for UICachedNodeIter<'a> {
+    type Item = &'a UICachedNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children.iter().rev());
+        Some(node)
+    }
+}
+
+/// A wrapper for windows `IUIAutomationTreeWalker` interface.
+///
+/// Exposes properties and methods that UI Automation client applications use to view and navigate the UI Automation elements on the desktop.
+#[derive(Clone)]
+pub struct UITreeWalker {
+    tree_walker: IUIAutomationTreeWalker
+}
+
+impl UITreeWalker {
+    /// Retrieves the parent element of the specified UI Automation element.
+    pub fn get_parent(&self, element: &UIElement) -> Result<UIElement> {
+        let parent: IUIAutomationElement;
+        unsafe {
+            parent = self.tree_walker.GetParentElement(&element.element)?;
+        }
+
+        Ok(UIElement::from(parent))
+    }
+
+    /// Retrieves the parent element of the specified UI Automation element, and caches properties and control patterns.
+    pub fn get_parent_build_cache(&self, element: &UIElement, cache_request: &UICacheRequest) -> Result<UIElement> {
+        let parent = unsafe {
+            self.tree_walker.GetParentElementBuildCache(element, cache_request)?
         };
         Ok(parent.into())
     }
@@ -1719,31 +2365,79 @@ pub enum UIMatcherMode {
     Content
 }
 
+/// Defines the order in which `UIMatcher` walks the UIAutomation tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UIMatcherStrategy {
+    /// Visits the whole subtree of an element before its next sibling.
+    #[default]
+    DepthFirst,
+    /// Visits all elements at one depth before going deeper, so shallow matches are found first.
+    BreadthFirst
+}
+
+/// Defines how `UIMatcher` sorts the matched elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UIMatcherOrder {
+    /// Sorts by the position in the UIAutomation tree (depth-first pre-order), whatever search strategy is used.
+    Tree,
+    /// Sorts by reading order: top-to-bottom, then left-to-right by the bounding rectangles.
+    Reading,
+    /// Sorts by the distance between the bounding rectangles and the rect, the nearest first.
+    Distance(Rect)
+}
+
 /// Defines filter conditions to match specific UI Element.
 ///
 /// `UIMatcher` can find first element or find all elements.
 pub struct UIMatcher {
     automation: UIAutomation,
     mode: UIMatcherMode,
+    strategy: UIMatcherStrategy,
+    order: Option<UIMatcherOrder>,
+    limit: Option<usize>,
     depth: u32,
     from: Option<UIElement>,
     filters: Vec<Box<dyn MatcherFilter>>,
+    exclusions: Vec<Box<dyn MatcherFilter>>,
+    prunes: Vec<Box<dyn MatcherFilter>>,
+    levels: Vec<(u32, Box<dyn MatcherFilter>)>,
     timeout: u64,
     interval: u64,
+    cancellation: Option<CancellationToken>,
+    process_scope: Option<ProcessScope>,
     debug: bool
 }
 
+/// The elements to search from, with their paths in the UIAutomation tree.
+type SearchRoots = Vec<(Vec<u32>, UIElement)>;
+
+/// Defines the processes whose top-level windows are searched by `UIMatcher`.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "process"), allow(dead_code))]
+enum ProcessScope {
+    Id(u32),
+    Name(String)
+}
+
 impl UIMatcher {
     /// Creates a matcher with `automation`.
     pub fn new(automation: UIAutomation) -> Self {
         UIMatcher {
             automation,
             mode: UIMatcherMode::Control,
+            strategy: UIMatcherStrategy::DepthFirst,
+            order: None,
+            limit: None,
             depth: 7,
             from: None,
             filters: Vec::new(),
+            exclusions: Vec::new(),
+            prunes: Vec::new(),
+            levels: Vec::new(),
             timeout: 3000,
             interval: 100,
+            cancellation: None,
+            process_scope: None,
             debug: false
         }
     }
@@ -1754,6 +2448,26 @@ impl UIMatcher {
         self
     }
 
+    /// Sets the searching strategy. `UIMatcherStrategy::DepthFirst` is default strategy.
+    pub fn strategy(mut self, strategy: UIMatcherStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sorts the matched elements by `order`. The elements are returned in the order they are found by default.
+    ///
+    /// All the elements under the root are searched when an order is set, because the first element can be found anywhere.
+    pub fn order_by(mut self, order: UIMatcherOrder) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Limits the number of matched elements. There is no limit by default.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
     /// Sets the root element of the UIAutomation tree whitch should be searched from.
     ///
     /// The root element is desktop by default.
@@ -1792,12 +2506,70 @@ impl UIMatcher {
         self
     }
 
+    /// Sets a token to cancel the searching. A cancelled error occurs as soon as the token is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Appends a filter condition which is used as `and` logic.
      pub fn filter(mut self, filter: Box<dyn MatcherFilter>) -> Self {
         self.filters.push(filter);
         self
     }
 
+    /// Appends an exclusion filter. The elements matching any exclusion filter are rejected, but their children are still searched.
+    pub fn exclude(mut self, filter: Box<dyn MatcherFilter>) -> Self {
+        self.exclusions.push(filter);
+        self
+    }
+
+    /// Appends a pruning filter. The children of the elements matching any pruning filter are not searched, but the elements themselves can still be matched.
+    ///
+    /// It is used to skip huge irrelevant subtrees, such as browser documents or data grids.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    /// use uiautomation::filters::ControlTypeFilter;
+    /// use uiautomation::types::ControlType;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let matcher = automation.create_matcher()
+    ///     .prune(Box::new(ControlTypeFilter { control_type: ControlType::DataGrid }))
+    ///     .prune(Box::new(ControlTypeFilter { control_type: ControlType::Document }))
+    ///     .name("OK");
+    /// let element = matcher.find_first();
+    /// ```
+    pub fn prune(mut self, filter: Box<dyn MatcherFilter>) -> Self {
+        self.prunes.push(filter);
+        self
+    }
+
+    /// Appends a path constraint for the elements at `level`. The root element is at level `0` and its children are at level `1`.
+    ///
+    /// The elements at `level` which do not match `filter` are neither matched nor searched into.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    /// use uiautomation::filters::ControlTypeFilter;
+    /// use uiautomation::types::ControlType;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let matcher = automation.create_matcher()
+    ///     .level(1, Box::new(ControlTypeFilter { control_type: ControlType::Window }))
+    ///     .level(2, Box::new(ControlTypeFilter { control_type: ControlType::Pane }))
+    ///     .control_type(ControlType::Edit);
+    /// let element = matcher.find_first();
+    /// ```
+    pub fn level(mut self, level: u32, filter: Box<dyn MatcherFilter>) -> Self {
+        self.levels.push((level, filter));
+        self
+    }
+
     /// Appends a filter function which is used as `and` logic.
     ///
     /// # Examples:
@@ -1855,6 +2627,40 @@ impl UIMatcher {
         self.filter(Box::new(condition))
     }
 
+    /// Append a filter whitch matches specific name after normalization, ignoring case, accelerator ampersands, shortcut suffixes,
+    /// trailing ellipses, extra spaces and full-width characters.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// // matches "Save &As...\tCtrl+Shift+S"
+    /// let item = automation.create_matcher().normalized_name("save as").find_first();
+    /// ```
+    pub fn normalized_name<S: AsRef<str>>(self, name: S) -> Self {
+        let condition = FuzzyNameFilter::new(name.as_ref(), NameNormalizer::default(), Similarity::default(), 1.0);
+        self.filter(Box::new(condition))
+    }
+
+    /// Append a filter whitch name is similar to specific name after normalization.
+    ///
+    /// The `threshold` is the minimal similarity from `0.0` to `1.0` measured by `similarity`.
+    pub fn fuzzy_name<S: AsRef<str>>(self, name: S, similarity: Similarity, threshold: f64) -> Self {
+        let condition = FuzzyNameFilter::new(name.as_ref(), NameNormalizer::default(), similarity, threshold);
+        self.filter(Box::new(condition))
+    }
+
+    /// Append a filter whitch matches any name of the logical `key` for the active UI language (ignore casesensitive).
+    ///
+    /// The names are looked up in the default `LocaleAliases` table, which is set by `LocaleAliases::set_default()`.
+    /// `key` itself is used as the name if it is not in the table.
+    pub fn name_key(self, key: &str) -> Self {
+        let condition = LocaleAliases::get_default().filter(key);
+        self.filter(Box::new(condition))
+    }
+
     /// Filters by classname.
     pub fn classname<S: Into<String>>(self, classname: S) -> Self {
         let condition = ClassNameFilter {
@@ -1879,10 +2685,149 @@ impl UIMatcher {
         self.filter(Box::new(condition))
     }
 
-    /// Clears all filters.
+    /// Filters the elements which have a descendant matched by `matcher`, such as a list item containing a specific text.
+    ///
+    /// The descendants are searched within the depth of `matcher`, which counts the element itself as the first level, like the search root.
+    /// Only the filters, exclusions and prunes of `matcher` are used.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    /// use uiautomation::types::ControlType;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let item = automation.create_matcher()
+    ///     .control_type(ControlType::ListItem)
+    ///     .has_descendant(automation.create_matcher().control_type(ControlType::Text).name("Error"))
+    ///     .find_first();
+    /// ```
+    pub fn has_descendant(self, matcher: UIMatcher) -> Self {
+        self.filter(Box::new(RelationFilter::new(TreeRelation::Descendant, matcher)))
+    }
+
+    /// Filters the elements which have a child matched by `matcher`.
+    pub fn has_child(self, matcher: UIMatcher) -> Self {
+        self.filter(Box::new(RelationFilter::new(TreeRelation::Child, matcher)))
+    }
+
+    /// Filters the elements which have an ancestor matched by `matcher`, such as a button inside a specific pane.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    /// use uiautomation::types::ControlType;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let button = automation.create_matcher()
+    ///     .control_type(ControlType::Button)
+    ///     .within(automation.create_matcher().control_type(ControlType::Pane).filter_fn(Box::new(|e: &uiautomation::UIElement| {
+    ///         Ok(e.get_automation_id()? == "SettingsPane")
+    ///     })))
+    ///     .find_first();
+    /// ```
+    pub fn within(self, matcher: UIMatcher) -> Self {
+        self.filter(Box::new(RelationFilter::new(TreeRelation::Ancestor, matcher)))
+    }
+
+    /// Filters the elements whose parent is matched by `matcher`.
+    pub fn parent(self, matcher: UIMatcher) -> Self {
+        self.filter(Box::new(RelationFilter::new(TreeRelation::Parent, matcher)))
+    }
+
+    /// Filters the controls labeled by `text`, such as edit boxes whose names are usually empty.
+    ///
+    /// The label is the `LabeledBy` element, or the nearest preceding `Text` sibling when `LabeledBy` is not set.
+    /// The texts are compared ignoring case, surrounding spaces and a trailing colon.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    /// use uiautomation::types::ControlType;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let edit = automation.create_matcher().control_type(ControlType::Edit).labeled("User Name").find_first();
+    /// ```
+    pub fn labeled<S: Into<String>>(self, text: S) -> Self {
+        let condition = LabeledFilter {
+            label: text.into(),
+            walker: self.automation.get_control_view_walker().ok()
+        };
+        self.filter(Box::new(condition))
+    }
+
+    /// Filters the elements on the right of `anchor`, which share some rows with it.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use uiautomation::core::UIAutomation;
+    /// use uiautomation::core::UIMatcherOrder;
+    /// use uiautomation::types::ControlType;
+    ///
+    /// let automation = UIAutomation::new().unwrap();
+    /// let label = automation.create_matcher().control_type(ControlType::Text).name("User Name:").find_first().unwrap();
+    /// let edit = automation.create_matcher()
+    ///     .control_type(ControlType::Edit)
+    ///     .right_of(&label)
+    ///     .order_by(UIMatcherOrder::Distance(label.get_bounding_rectangle().unwrap()))
+    ///     .find_first();
+    /// ```
+    pub fn right_of(self, anchor: &UIElement) -> Self {
+        self.filter(Box::new(SpatialFilter::new(SpatialRelation::RightOf, anchor)))
+    }
+
+    /// Filters the elements on the left of `anchor`, which share some rows with it.
+    pub fn left_of(self, anchor: &UIElement) -> Self {
+        self.filter(Box::new(SpatialFilter::new(SpatialRelation::LeftOf, anchor)))
+    }
+
+    /// Filters the elements below `anchor`, which share some columns with it.
+    pub fn below(self, anchor: &UIElement) -> Self {
+        self.filter(Box::new(SpatialFilter::new(SpatialRelation::Below, anchor)))
+    }
+
+    /// Filters the elements above `anchor`, which share some columns with it.
+    pub fn above(self, anchor: &UIElement) -> Self {
+        self.filter(Box::new(SpatialFilter::new(SpatialRelation::Above, anchor)))
+    }
+
+    /// Filters the elements within `max_distance` pixels of `anchor`. The anchor itself is not matched.
+    pub fn near(self, anchor: &UIElement, max_distance: i32) -> Self {
+        self.filter(Box::new(SpatialFilter::new(SpatialRelation::Near(max_distance), anchor)))
+    }
+
+    /// Filters the elements completely inside `rect`.
+    pub fn inside(self, rect: Rect) -> Self {
+        self.filter(Box::new(SpatialFilter::from_rect(SpatialRelation::Inside, rect)))
+    }
+
+    /// Searches only beneath the top-level windows of the process `pid` and its sub processes.
+    ///
+    /// The top-level windows are located by a native `ProcessId` condition, so the windows of other applications are not visited.
+    #[cfg(feature = "process")]
+    pub fn in_process(mut self, pid: u32) -> Self {
+        self.process_scope = Some(ProcessScope::Id(pid));
+        self
+    }
+
+    /// Searches only beneath the top-level windows of the processes whose executable file name is `name` (ignore casesensitive), such as `notepad.exe`,
+    /// including their sub processes.
+    #[cfg(feature = "process")]
+    pub fn in_process_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.process_scope = Some(ProcessScope::Name(name.into()));
+        self
+    }
+
+    /// Clears all filters, including exclusion, pruning and level filters.
     pub fn reset(mut self) -> Self {
         // self.condition = None;
         self.filters.clear();
+        self.exclusions.clear();
+        self.prunes.clear();
+        self.levels.clear();
         self
     }
 
@@ -1894,18 +2839,34 @@ impl UIMatcher {
 
     /// Finds first element.
     pub fn find_first(&self) -> Result<UIElement> {
-        let elements = self.find(true)?;
+        self.find_nth(0)
+    }
 
-        if elements.is_empty() {
+    /// Finds the `n`th (starting from `0`) element.
+    pub fn find_nth(&self, n: usize) -> Result<UIElement> {
+        let mut elements = self.find(Some(n + 1))?;
+
+        if elements.len() <= n {
             Err(Error::new(ERR_NOTFOUND, "can not find element"))
         } else {
-            Ok(elements[0].clone())
+            Ok(elements.swap_remove(n))
+        }
+    }
+
+    /// Finds last element.
+    pub fn find_last(&self) -> Result<UIElement> {
+        let elements = self.find(None)?;
+
+        if let Some(element) = elements.last() {
+            Ok(element.clone())
+        } else {
+            Err(Error::new(ERR_NOTFOUND, "can not find element"))
         }
     }
 
     /// Finds all elements.
     pub fn find_all(&self) -> Result<Vec<UIElement>> {
-        let elements = self.find(false)?;
+        let elements = self.find(None)?;
 
         if elements.is_empty() {
             Err(Error::new(ERR_NOTFOUND, "can not find element"))
@@ -1914,8 +2875,26 @@ impl UIMatcher {
         }
     }
 
-    fn find(&self, first_only: bool) -> Result<Vec<UIElement>> {
-        let mut elements: Vec<UIElement> = Vec::new();
+    /// Counts the matched elements at the moment, without waiting for timeout.
+    pub fn count(&self) -> Result<usize> {
+        let pids = self.get_process_ids()?;
+        let elements = self.search_once(None, pids.as_deref())?;
+        Ok(elements.len())
+    }
+
+    /// Checks whether any element is matched in `timeout`. Errors are regarded as not found.
+    pub fn exists(&self) -> bool {
+        self.find_first().is_ok()
+    }
+
+    fn find(&self, wanted: Option<usize>) -> Result<Vec<UIElement>> {
+        // the wanted elements can never be found within the limit, so it does not wait for timeout.
+        if self.limit.is_some_and(|limit| limit < wanted.unwrap_or(1)) {
+            return Err(Error::new(ERR_NOTFOUND, "can not find element beyond the limit"));
+        }
+
+        // the processes are looked up once, so the retries do not take a snapshot each time.
+        let pids = self.get_process_ids()?;
         let start = Local::now().timestamp_millis();
         loop {
             // if self.debug {
@@ -1928,11 +2907,10 @@ impl UIMatcher {
                 log_debug!("Try to match element...");
             }
 
-            let (root, walker) = self.prepare()?;
-            self.search(&walker, &root, &mut elements, 1, first_only)?;
+            let elements = self.search_once(wanted, pids.as_deref())?;
 
-            if !elements.is_empty() || self.timeout <= 0 {
-                break;
+            if elements.len() >= wanted.unwrap_or(1) || self.timeout <= 0 {
+                return Ok(elements);
             }
 
             let now = Local::now().timestamp_millis();
@@ -1940,45 +2918,168 @@ impl UIMatcher {
                 return Err(Error::new(ERR_TIMEOUT, "find element time out"));
             }
 
-            sleep(Duration::from_millis(self.interval));
+            if let Some(ref token) = self.cancellation {
+                token.sleep(Duration::from_millis(self.interval))?;
+            } else {
+                sleep(Duration::from_millis(self.interval));
+            }
+        }
+    }
+
+    /// Retrieves the timeout in millionseconds.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_timeout(&self) -> u64 {
+        self.timeout
+    }
+
+    /// Retrieves the interval in millionseconds for retrying.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Searches the tree once without retrying, and returns no more than `wanted` elements.
+    ///
+    /// `pids` are the processes retrieved by `get_process_ids()`, which limit the top level windows to search.
+    pub(crate) fn search_once(&self, wanted: Option<usize>, pids: Option<&[u32]>) -> Result<Vec<UIElement>> {
+        let limit = match (wanted, self.limit) {
+            (Some(wanted), Some(limit)) => Some(wanted.min(limit)),
+            (wanted, limit) => wanted.or(limit)
+        };
+        let search_limit = if self.order.is_some() {
+            None
+        } else {
+            limit
+        };
+
+        let (roots, depth, walker) = self.prepare(pids)?;
+        let mut found: Vec<(Vec<u32>, UIElement)> = Vec::new();
+        match self.strategy {
+            UIMatcherStrategy::DepthFirst => {
+                for (mut path, root) in roots {
+                    self.search(&walker, &root, &mut found, depth, &mut path, search_limit)?;
+                    if search_limit.is_some_and(|limit| found.len() >= limit) {
+                        break;
+                    }
+                }
+            },
+            UIMatcherStrategy::BreadthFirst => self.search_breadth(&walker, roots, depth, &mut found, search_limit)?,
+        };
+
+        match self.order {
+            Some(UIMatcherOrder::Tree) => found.sort_by(|(a, _), (b, _)| a.cmp(b)),
+            Some(UIMatcherOrder::Reading) => found.sort_by_cached_key(|(_, e)| {
+                let rect = e.get_bounding_rectangle().unwrap_or_default();
+                (rect.get_top(), rect.get_left())
+            }),
+            Some(UIMatcherOrder::Distance(anchor)) => {
+                let mut ranked: Vec<(f64, (Vec<u32>, UIElement))> = found.into_iter().map(|item| {
+                    let distance = item.1.get_bounding_rectangle().map_or(f64::MAX, |rect| rect.distance_to(&anchor));
+                    (distance, item)
+                }).collect();
+                ranked.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+                found = ranked.into_iter().map(|(_, item)| item).collect();
+            },
+            None => {}
+        };
+
+        let mut elements: Vec<UIElement> = found.into_iter().map(|(_, e)| e).collect();
+        if let Some(limit) = limit {
+            elements.truncate(limit);
         }
 
         Ok(elements)
     }
 
-    fn prepare(&self) -> Result<(UIElement, UITreeWalker)> {
+    fn prepare(&self, pids: Option<&[u32]>) -> Result<(SearchRoots, u32, UITreeWalker)> {
         let root = if let Some(ref from) = self.from {
             from.clone()
         } else {
             self.automation.get_root_element()?
         };
 
-        let walker = match self.mode {
-            UIMatcherMode::Raw => self.automation.create_tree_walker()?,
-            UIMatcherMode::Control => self.automation.filter_tree_walker(self.automation.get_control_view_condition()?)?,
-            UIMatcherMode::Content => self.automation.filter_tree_walker(self.automation.get_content_view_condition()?)?,
+        let view_condition = self.get_view_condition()?;
+        let walker = self.create_walker(view_condition.clone())?;
+
+        if let Some(pids) = pids {
+            let mut condition = self.automation.create_false_condition()?;
+            for &pid in pids {
+                let pid_condition = self.automation.create_property_condition(UIProperty::ProcessId, Variant::from(pid as i32), None)?;
+                condition = self.automation.create_or_condition(condition, pid_condition)?;
+            }
+            if let Some(view_condition) = view_condition {
+                condition = self.automation.create_and_condition(condition, view_condition)?;
+            }
+
+            let windows = root.find_all(TreeScope::Children, &condition)?;
+            let roots = windows.into_iter().enumerate().map(|(index, window)| (vec![index as u32], window)).collect();
+            Ok((roots, 2, walker))
+        } else {
+            Ok((vec![(Vec::new(), root)], 1, walker))
+        }
+    }
+
+    fn get_view_condition(&self) -> Result<Option<UICondition>> {
+        Ok(match self.mode {
+            UIMatcherMode::Raw => None,
+            UIMatcherMode::Control => Some(self.automation.get_control_view_condition()?),
+            UIMatcherMode::Content => Some(self.automation.get_content_view_condition()?),
+        })
+    }
+
+    fn create_walker(&self, view_condition: Option<UICondition>) -> Result<UITreeWalker> {
+        match view_condition {
+            Some(condition) => self.automation.filter_tree_walker(condition),
+            None => self.automation.create_tree_walker(),
+        }
+    }
+
+    /// Retrieves the ids of the processes and their sub processes to search in, or `None` if the search is not limited to processes.
+    #[cfg(feature = "process")]
+    pub(crate) fn get_process_ids(&self) -> Result<Option<Vec<u32>>> {
+        let Some(ref scope) = self.process_scope else {
+            return Ok(None);
+        };
+
+        let snapshot = super::processes::ProcessSnapshot::new()?;
+        let pids = match scope {
+            ProcessScope::Id(pid) => vec![*pid],
+            ProcessScope::Name(name) => snapshot.find_by_name(name)
         };
 
-        Ok((root, walker))
+        Ok(Some(snapshot.with_sub_processes(&pids)))
+    }
+
+    #[cfg(not(feature = "process"))]
+    pub(crate) fn get_process_ids(&self) -> Result<Option<Vec<u32>>> {
+        Ok(None)
     }
 
-    fn search(&self, walker: &UITreeWalker, element: &UIElement, elements: &mut Vec<UIElement>, depth: u32, first_only: bool) -> Result<()> {
+    fn search(&self, walker: &UITreeWalker, element: &UIElement, found: &mut Vec<(Vec<u32>, UIElement)>, depth: u32, path: &mut Vec<u32>, limit: Option<usize>) -> Result<()> {
+        if !self.is_on_level(element, depth)? {
+            return Ok(());
+        }
+
         if self.is_matched(element)? {
-            elements.push(element.clone());
+            found.push((path.clone(), element.clone()));
 
-            if first_only {
+            if limit.is_some_and(|limit| found.len() >= limit) {
                 return Ok(());
             }
         }
 
-        if depth < self.depth {
+        if depth < self.depth && !self.is_pruned(element)? {
+            let mut index = 0;
             let mut next = walker.get_first_child(element);
             while let Ok(ref child) = next {
-                self.search(walker, child, elements, depth + 1, first_only)?;
-                if first_only && !elements.is_empty() {
+                path.push(index);
+                self.search(walker, child, found, depth + 1, path, limit)?;
+                path.pop();
+                if limit.is_some_and(|limit| found.len() >= limit) {
                     return Ok(());
                 }
 
+                index += 1;
                 next = walker.get_next_sibling(child);
             }
         }
@@ -1986,13 +3087,56 @@ impl UIMatcher {
         Ok(())
     }
 
+    fn search_breadth(&self, walker: &UITreeWalker, roots: SearchRoots, depth: u32, found: &mut Vec<(Vec<u32>, UIElement)>, limit: Option<usize>) -> Result<()> {
+        let mut queue: VecDeque<(UIElement, u32, Vec<u32>)> = roots.into_iter().map(|(path, root)| (root, depth, path)).collect();
+
+        while let Some((element, depth, path)) = queue.pop_front() {
+            if !self.is_on_level(&element, depth)? {
+                continue;
+            }
+
+            if self.is_matched(&element)? {
+                found.push((path.clone(), element.clone()));
+
+                if limit.is_some_and(|limit| found.len() >= limit) {
+                    return Ok(());
+                }
+            }
+
+            if depth < self.depth && !self.is_pruned(&element)? {
+                let mut index = 0;
+                let mut next = walker.get_first_child(&element);
+                while let Ok(child) = next {
+                    next = walker.get_next_sibling(&child);
+
+                    let mut child_path = path.clone();
+                    child_path.push(index);
+                    queue.push_back((child, depth + 1, child_path));
+
+                    index += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn is_matched(&self, element: &UIElement) -> Result<bool> {
+        if let Some(ref token) = self.cancellation {
+            token.check()?;
+        }
+
         if let Some(ref root) = self.from {
             if self.automation.compare_elements(root, element)? {
                 return Ok(false);
             }
         }
 
+        self.judge_element(element)
+    }
+
+    /// Judges `element` by the filters and the exclusions, without checking the root element.
+    fn judge_element(&self, element: &UIElement) -> Result<bool> {
         // let ret = if let Some(ref condition) = self.condition {
         //     condition.judge(element)?
         // } else {
@@ -2009,6 +3153,16 @@ impl UIMatcher {
             }
         }
 
+        if ret {
+            for exclusion in self.exclusions.iter() {
+                if exclusion.judge(element)? {
+                    ret = false;
+                    failed_filter = self.filters.len();
+                    break;
+                }
+            }
+        }
+
         if self.debug {
             // #[cfg(feature = "log") ]
             // log::debug!("{:?} -> {} in filter {}", element, ret, failed_filter);
@@ -2019,6 +3173,33 @@ impl UIMatcher {
 
         Ok(ret)
     }
+
+    fn is_pruned(&self, element: &UIElement) -> Result<bool> {
+        for prune in self.prunes.iter() {
+            if prune.judge(element)? {
+                if self.debug {
+                    log_debug!("{:?} is pruned", element);
+                }
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn is_on_level(&self, element: &UIElement, depth: u32) -> Result<bool> {
+        let level = depth - 1;
+        for (filter_level, filter) in self.levels.iter() {
+            if *filter_level == level && !filter.judge(element)? {
+                if self.debug {
+                    log_debug!("{:?} is not on the path of level {}", element, level);
+                }
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
 }
 
 impl Debug for UIMatcher {
@@ -2026,16 +3207,221 @@ impl Debug for UIMatcher {
         f.debug_struct("UIMatcher")
             .field("automation", &self.automation)
             .field("mode", &self.mode)
+            .field("strategy", &self.strategy)
+            .field("order", &self.order)
+            .field("limit", &self.limit)
             .field("depth", &self.depth)
             .field("from", &self.from)
             .field("filters", &format!("({} filers)", self.filters.len()))
+            .field("exclusions", &format!("({} filers)", self.exclusions.len()))
+            .field("prunes", &format!("({} filers)", self.prunes.len()))
+            .field("levels", &format!("({} filers)", self.levels.len()))
             .field("timeout", &self.timeout)
             .field("interval", &self.interval)
+            .field("cancellation", &self.cancellation)
+            .field("process_scope", &self.process_scope)
             .field("debug", &self.debug)
         .finish()
     }
 }
 
+/// A persistent reference to a UI element, made up of its runtime id, process id, window handle and a locator.
+///
+/// The reference can be converted to a string and parsed back, and it is resolved by `UIAutomation::resolve()`.
+/// The locator (control type, automation id, classname and name) is used when the runtime id is not valid anymore.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::UIAutomation;
+/// use uiautomation::core::ElementRef;
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let element = automation.create_matcher().classname("Notepad").find_first().unwrap();
+/// let text = element.to_ref(&automation).unwrap().to_string();
+///
+/// let reference: ElementRef = text.parse().unwrap();
+/// let resolved = automation.resolve(&reference).unwrap();
+/// assert_eq!(element, resolved);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ElementRef {
+    pub runtime_id: Vec<i32>,
+    pub process_id: u32,
+    pub window_handle: isize,
+    pub control_type: Option<ControlType>,
+    pub automation_id: String,
+    pub classname: String,
+    pub name: String
+}
+
+impl ElementRef {
+    fn escape(value: &str) -> String {
+        value.replace('\\', "\\\\").replace(';', "\\;")
+    }
+
+    fn split(text: &str) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        field.push(next);
+                    }
+                },
+                ';' => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c)
+            }
+        }
+        fields.push(field);
+        fields
+    }
+}
+
+impl Display for ElementRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let runtime_id: Vec<String> = self.runtime_id.iter().map(|id| id.to_string()).collect();
+        // the handle is written as unsigned, so a negative handle is written as its two's complement.
+        write!(f, "rid={};pid={};hwnd={:#x}", runtime_id.join("."), self.process_id, self.window_handle as usize)?;
+        if let Some(control_type) = self.control_type {
+            write!(f, ";type={}", control_type as i32)?;
+        }
+        write!(f, ";aid={};class={};name={}", Self::escape(&self.automation_id), Self::escape(&self.classname), Self::escape(&self.name))
+    }
+}
+
+impl FromStr for ElementRef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |field: &str| Error::new(ERR_FORMAT, &format!("invalid element reference field: {}", field));
+
+        let mut reference = ElementRef::default();
+        for field in Self::split(s) {
+            let Some((key, value)) = field.split_once('=') else {
+                return Err(invalid(&field));
+            };
+            match key {
+                "rid" => {
+                    reference.runtime_id = value.split('.')
+                        .filter(|id| !id.is_empty())
+                        .map(|id| id.parse::<i32>())
+                        .collect::<std::result::Result<_, _>>()
+                        .map_err(|_| invalid(&field))?;
+                },
+                "pid" => reference.process_id = value.parse().map_err(|_| invalid(&field))?,
+                "hwnd" => {
+                    let hex = value.trim_start_matches("0x");
+                    reference.window_handle = usize::from_str_radix(hex, 16).map_err(|_| invalid(&field))? as isize;
+                },
+                "type" => {
+                    let id: i32 = value.parse().map_err(|_| invalid(&field))?;
+                    reference.control_type = Some(ControlType::try_from(UIA_CONTROLTYPE_ID(id))?);
+                },
+                "aid" => reference.automation_id = value.to_string(),
+                "class" => reference.classname = value.to_string(),
+                "name" => reference.name = value.to_string(),
+                _ => return Err(invalid(&field))
+            }
+        }
+
+        Ok(reference)
+    }
+}
+
+/// Defines the tree relation between an element and the elements matched by a nested `UIMatcher`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TreeRelation {
+    Child,
+    Descendant,
+    Parent,
+    Ancestor
+}
+
+/// Filters the elements by a nested `UIMatcher` applied to their relatives.
+struct RelationFilter {
+    relation: TreeRelation,
+    matcher: UIMatcher,
+    walker: RefCell<Option<UITreeWalker>>
+}
+
+impl RelationFilter {
+    fn new(relation: TreeRelation, matcher: UIMatcher) -> Self {
+        Self {
+            relation,
+            matcher,
+            walker: RefCell::new(None)
+        }
+    }
+
+    fn get_walker(&self) -> Result<UITreeWalker> {
+        if let Some(ref walker) = *self.walker.borrow() {
+            return Ok(walker.clone());
+        }
+
+        let walker = self.matcher.create_walker(self.matcher.get_view_condition()?)?;
+        *self.walker.borrow_mut() = Some(walker.clone());
+
+        Ok(walker)
+    }
+
+    /// Checks the descendants of `element` at `depth`, whose children are at `depth + 1`, the same as `UIMatcher::search()`.
+    fn has_descendant(&self, walker: &UITreeWalker, element: &UIElement, depth: u32) -> Result<bool> {
+        if depth >= self.matcher.depth {
+            return Ok(false);
+        }
+
+        let mut next = walker.get_first_child(element);
+        while let Ok(child) = next {
+            if self.matcher.judge_element(&child)? {
+                return Ok(true);
+            }
+            if !self.matcher.is_pruned(&child)? && self.has_descendant(walker, &child, depth + 1)? {
+                return Ok(true);
+            }
+
+            next = walker.get_next_sibling(&child);
+        }
+
+        Ok(false)
+    }
+}
+
+impl MatcherFilter for RelationFilter {
+    fn judge(&self, element: &UIElement) -> Result<bool> {
+        let walker = self.get_walker()?;
+        match self.relation {
+            TreeRelation::Child => {
+                let mut next = walker.get_first_child(element);
+                while let Ok(child) = next {
+                    if self.matcher.judge_element(&child)? {
+                        return Ok(true);
+                    }
+                    next = walker.get_next_sibling(&child);
+                }
+                Ok(false)
+            },
+            TreeRelation::Descendant => self.has_descendant(&walker, element, 1),
+            TreeRelation::Parent => match walker.get_parent(element) {
+                Ok(parent) => self.matcher.judge_element(&parent),
+                Err(_) => Ok(false)
+            },
+            TreeRelation::Ancestor => {
+                let mut next = walker.get_parent(element);
+                while let Ok(parent) = next {
+                    if self.matcher.judge_element(&parent)? {
+                        return Ok(true);
+                    }
+                    next = walker.get_parent(&parent);
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
 /// This is the trait for conditions used in filtering when searching for elements in the UI Automation tree.
 pub trait IUICondition<T: Interface>: Sized + From<T> + Into<T> + AsRef<T> {
 }
@@ -2488,6 +3874,9 @@ mod tests {
     use crate::filters::MatcherFilter;
     use crate::types::TreeScope;
 
+    use super::RelationFilter;
+    use super::TreeRelation;
+
     fn print_element(element: &UIElement) {
         println!("Name: {}", element.get_name().unwrap());
         println!("ControlType: {:?}", element.get_control_type().unwrap());
@@ -2618,6 +4007,147 @@ mod tests {
         assert!(item.is_err());
     }
 
+    #[test]
+    fn test_find_breadth_first() {
+        use super::UIMatcherOrder;
+        use super::UIMatcherStrategy;
+
+        let automation = UIAutomation::new().unwrap();
+        let matcher = automation.create_matcher().timeout(0).depth(3).control_type(ControlType::Pane);
+        let count = matcher.count().unwrap();
+
+        let matcher = matcher.strategy(UIMatcherStrategy::BreadthFirst).order_by(UIMatcherOrder::Reading);
+        assert_eq!(matcher.count().unwrap(), count);
+        assert_eq!(matcher.exists(), count > 0);
+        if count > 0 {
+            let last = matcher.find_last().unwrap();
+            let nth = matcher.find_nth(count - 1).unwrap();
+            assert!(automation.compare_elements(&last, &nth).unwrap());
+            assert!(matcher.find_nth(count).is_err());
+        }
+
+        let limited = automation.create_matcher().timeout(0).depth(3).control_type(ControlType::Pane).limit(1);
+        assert!(limited.count().unwrap() <= 1);
+
+        let start = std::time::Instant::now();
+        let limited = automation.create_matcher().timeout(60000).depth(3).limit(1);
+        assert_eq!(limited.find_nth(1).unwrap_err().code(), crate::errors::ERR_NOTFOUND);
+        assert_eq!(limited.limit(0).find_first().unwrap_err().code(), crate::errors::ERR_NOTFOUND);
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_prune_search() {
+        use crate::filters::ControlTypeFilter;
+
+        let automation = UIAutomation::new().unwrap();
+        let all = automation.create_matcher().timeout(0).depth(3).count().unwrap();
+        let pruned = automation.create_matcher().timeout(0).depth(3)
+            .prune(Box::new(ControlTypeFilter { control_type: ControlType::Pane }))
+            .count().unwrap();
+        assert!(pruned <= all);
+
+        let windows = automation.create_matcher().timeout(0).depth(2)
+            .level(1, Box::new(ControlTypeFilter { control_type: ControlType::Window }))
+            .exclude(Box::new(ControlTypeFilter { control_type: ControlType::Pane }));
+        for window in windows.find_all().unwrap_or_default() {
+            assert_eq!(window.get_control_type().unwrap(), ControlType::Window);
+        }
+    }
+
+    #[test]
+    fn test_element_ref() {
+        use std::collections::HashSet;
+
+        use super::ElementRef;
+
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+        let elements = automation.create_matcher().timeout(0).depth(2).find_all().unwrap_or_default();
+        let mut set: HashSet<UIElement> = elements.iter().cloned().collect();
+        set.extend(elements.iter().cloned());
+        assert_eq!(set.len(), elements.len());
+
+        for element in elements.iter().take(3) {
+            let text = element.to_ref(&automation).unwrap().to_string();
+            println!("{} -> {}", element, text);
+
+            let reference: ElementRef = text.parse().unwrap();
+            let resolved = automation.resolve(&reference).unwrap();
+            assert_eq!(*element, resolved);
+            assert_ne!(resolved, root);
+        }
+
+        let reference = ElementRef {
+            runtime_id: vec![42, 1234, 5],
+            process_id: 100,
+            window_handle: 0x1A2B,
+            control_type: Some(ControlType::Edit),
+            automation_id: String::from("a;b"),
+            classname: String::from("Edit"),
+            name: String::from("C:\\Temp=1")
+        };
+        assert_eq!(reference.to_string().parse::<ElementRef>().unwrap(), reference);
+
+        let reference = ElementRef {
+            window_handle: -2,
+            ..reference
+        };
+        assert!(reference.to_string().contains(&format!("hwnd={:#x}", usize::MAX - 1)));
+        assert_eq!(reference.to_string().parse::<ElementRef>().unwrap(), reference);
+    }
+
+    #[test]
+    fn test_ancestry() {
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+        let Ok(element) = automation.create_matcher().timeout(0).depth(4).control_type(ControlType::Button).find_first() else {
+            return;
+        };
+
+        let window = element.top_level_window(&automation).unwrap();
+        assert_eq!(window.parent(&automation).unwrap(), root);
+        assert_eq!(element.ancestors(&automation).unwrap().last(), Some(&root));
+
+        let closest = element.closest(&automation, &crate::filters::FnFilter { filter: Box::new(|e: &UIElement| Ok(e.get_control_type()? == ControlType::Button)) }).unwrap();
+        assert_eq!(closest, element);
+
+        let index = element.index_in_parent(&automation).unwrap();
+        println!("{} in {} at {}", element, window, index);
+
+        #[cfg(feature = "process")]
+        println!("{:?}", element.process_info().unwrap());
+    }
+
+    #[test]
+    fn test_relation_search() {
+        let automation = UIAutomation::new().unwrap();
+        let buttons = automation.create_matcher().timeout(0).depth(5)
+            .control_type(ControlType::Button)
+            .within(automation.create_matcher().classname("Shell_TrayWnd"))
+            .find_all().unwrap_or_default();
+        for button in buttons.iter() {
+            println!("{}", button);
+        }
+
+        let taskbar = automation.create_matcher().timeout(0).depth(2)
+            .classname("Shell_TrayWnd")
+            .has_descendant(automation.create_matcher().control_type(ControlType::Button).depth(4))
+            .find_first();
+        assert_eq!(taskbar.is_ok(), !buttons.is_empty());
+
+        // the nested matcher searches the same levels as the outer matcher of the same depth.
+        if let Ok(taskbar) = taskbar {
+            for depth in 1..5 {
+                let outer = automation.create_matcher().from_ref(&taskbar).timeout(0).depth(depth).control_type(ControlType::Button).exists();
+                let nested = RelationFilter::new(TreeRelation::Descendant, automation.create_matcher().control_type(ControlType::Button).depth(depth))
+                    .judge(&taskbar)
+                    .unwrap();
+                assert_eq!(outer, nested, "depth {}", depth);
+            }
+        }
+    }
+
     #[test]
     fn test_automation_id() {
         let automation = UIAutomation::new().unwrap();
@@ -2655,6 +4185,46 @@ mod tests {
     //     println!("Window Rect Array = [{}, {}, {}, {}]", l, t, r, b);
     // }
 
+    #[test]
+    #[cfg(feature = "pattern")]
+    fn test_build_cached_tree() {
+        use crate::types::UIProperty;
+
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+        let tree = root.build_cached_tree(&automation, TreeScope::Children, &[UIProperty::Name, UIProperty::ControlType], &[]).unwrap();
+        assert_eq!(tree.get_depth(), 0);
+        assert!(tree.get_name().is_ok());
+        assert!(!tree.get_children().is_empty());
+        for child in tree.get_children() {
+            assert_eq!(child.get_depth(), 1);
+            assert!(child.get_children().is_empty());
+            assert!(child.get_name().is_ok());
+            assert!(child.get_control_type().is_ok());
+        }
+
+        let nodes: Vec<_> = tree.iter().collect();
+        assert_eq!(nodes.len(), tree.get_children().len() + 1);
+        assert!(std::ptr::eq(nodes[0], &tree));
+    }
+
+    #[test]
+    fn test_cancel_matcher() {
+        let token = crate::cancellations::CancellationToken::new();
+        let automation = UIAutomation::new().unwrap();
+        let matcher = automation.create_matcher().name("You can find nothing!").timeout(60000).cancellation(token.clone());
+
+        let stop = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            stop.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        assert_eq!(matcher.find_first().unwrap_err().code(), crate::errors::ERR_CANCELLED);
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
     #[test]
     fn test_create() {
         let _ = UIAutomation::new();
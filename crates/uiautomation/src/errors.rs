@@ -26,6 +26,12 @@ pub const ERR_INVALID_OBJECT: i32 = 7;
 pub const ERR_ALREADY_RUNNING: i32 = 8;
 /// Error invalid argument.
 pub const ERR_INVALID_ARG: i32 = 9;
+/// Error occurs when the operation is cancelled.
+pub const ERR_CANCELLED: i32 = 10;
+/// Error occurs when a buffer is full.
+pub const ERR_OVERFLOW: i32 = 11;
+/// Error occurs when an event handler panics.
+pub const ERR_PANIC: i32 = 12;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Error {
@@ -1,25 +1,47 @@
+use windows::Win32::UI::Accessibility::IUIAutomationActiveTextPositionChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationActiveTextPositionChangedEventHandler_Impl;
+use windows::Win32::UI::Accessibility::IUIAutomationChangesEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationChangesEventHandler_Impl;
 use windows::Win32::UI::Accessibility::IUIAutomationElement;
 use windows::Win32::UI::Accessibility::IUIAutomationEventHandler;
 use windows::Win32::UI::Accessibility::IUIAutomationEventHandler_Impl;
 use windows::Win32::UI::Accessibility::IUIAutomationFocusChangedEventHandler;
 use windows::Win32::UI::Accessibility::IUIAutomationFocusChangedEventHandler_Impl;
+use windows::Win32::UI::Accessibility::IUIAutomationNotificationEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationNotificationEventHandler_Impl;
 use windows::Win32::UI::Accessibility::IUIAutomationPropertyChangedEventHandler;
 use windows::Win32::UI::Accessibility::IUIAutomationPropertyChangedEventHandler_Impl;
 use windows::Win32::UI::Accessibility::IUIAutomationStructureChangedEventHandler;
 use windows::Win32::UI::Accessibility::IUIAutomationStructureChangedEventHandler_Impl;
+use windows::Win32::UI::Accessibility::IUIAutomationTextEditTextChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationTextEditTextChangedEventHandler_Impl;
+use windows::Win32::UI::Accessibility::IUIAutomationTextRange;
 use windows::Win32::UI::Accessibility::UIA_EVENT_ID;
 use windows::Win32::UI::Accessibility::UIA_PROPERTY_ID;
+use windows::Win32::UI::Accessibility::UiaChangeInfo;
+use windows::core::BSTR;
 use windows_core::implement;
 
+use crate::patterns::UITextRange;
+use crate::types::NotificationKind;
+use crate::types::NotificationProcessing;
 use crate::types::StructureChangeType;
+use crate::types::TextEditChangeType;
 use crate::variants::SafeArray;
 use crate::variants::Variant;
+use crate::Result;
 use crate::UIElement;
 
+use super::CustomActiveTextPositionChangedEventHandlerFn;
+use super::CustomChangesEventHandlerFn;
 use super::CustomEventHandlerFn;
 use super::CustomFocusChangedEventHandlerFn;
+use super::CustomNotificationEventHandlerFn;
 use super::CustomPropertyChangedEventHandlerFn;
 use super::CustomStructureChangedEventHandlerFn;
+use super::CustomTextEditTextChangedEventHandlerFn;
+use super::UIChangeInfo;
+use super::UIEventType;
 
 #[implement(IUIAutomationEventHandler)]
 pub struct AutomationEventHandler {
@@ -28,16 +50,18 @@ pub struct AutomationEventHandler {
 
 impl IUIAutomationEventHandler_Impl for AutomationEventHandler_Impl {
     fn HandleAutomationEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>, eventid: UIA_EVENT_ID) -> windows_core::Result<()> {
-        if let Some(e) = sender.as_ref() {
-            let element = UIElement::from(e);
-            let handler = &self.handler;
-            match eventid.try_into() {
-                Ok(event_id) => handler(&element, event_id).map_err(|e| e.into()),
-                Err(e) => Err(e.into())
+        super::handle_event(eventid.0, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let element = UIElement::from(e);
+                let handler = &self.handler;
+                match eventid.try_into() {
+                    Ok(event_id) => handler(&element, event_id),
+                    Err(e) => Err(e)
+                }
+            } else {
+                Ok(())
             }
-        } else {
-            Ok(())
-        }
+        })
     }
 }
 
@@ -56,17 +80,19 @@ pub struct AutomationPropertyChangedEventHandler {
 
 impl IUIAutomationPropertyChangedEventHandler_Impl for AutomationPropertyChangedEventHandler_Impl {
     fn HandlePropertyChangedEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>, propertyid: UIA_PROPERTY_ID, newvalue: &windows::Win32::System::Variant::VARIANT) -> windows_core::Result<()> {
-        if let Some(e) = sender.as_ref() {
-            let element = UIElement::from(e);
-            let value = Variant::from(newvalue);
-            let handler = &self.handler;
-            match propertyid.try_into() {
-                Ok(property_id) => handler(&element, property_id, value).map_err(|e| e.into()),
-                Err(e) => Err(e.into()),
+        super::handle_event(UIEventType::AutomationPropertyChanged as i32, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let element = UIElement::from(e);
+                let value = Variant::from(newvalue);
+                let handler = &self.handler;
+                match propertyid.try_into() {
+                    Ok(property_id) => handler(&element, property_id, value),
+                    Err(e) => Err(e),
+                }
+            } else {
+                Ok(())
             }
-        } else {
-            Ok(())
-        }
+        })
     }
 }
 
@@ -85,27 +111,28 @@ pub struct AutomationStructureChangedEventHandler {
 
 impl IUIAutomationStructureChangedEventHandler_Impl for AutomationStructureChangedEventHandler_Impl {
     fn HandleStructureChangedEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>, changetype: windows::Win32::UI::Accessibility::StructureChangeType, runtimeid: *const windows::Win32::System::Com::SAFEARRAY) -> windows_core::Result<()> {
-        if let Some(e) = sender.as_ref() {
-            let handler = &self.handler;
-            let element = UIElement::from(e);
-            let arr = SafeArray::from(runtimeid);
-            let change_type: StructureChangeType = match changetype.try_into() {
-                Ok(change_type) => change_type,
-                Err(e) => return Err(e.into()),
-            };
-            let ret = if arr.is_null() {
-                handler(&element, change_type, None)
-            } else {
-                let runtime_id: Vec<i32> = match arr.try_into() {
-                    Ok(arr) => arr,
-                    Err(e) => return  Err(e.into())
+        super::handle_event(UIEventType::StructureChanged as i32, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let handler = &self.handler;
+                let element = UIElement::from(e);
+                let arr = SafeArray::from(runtimeid);
+                let change_type: StructureChangeType = match changetype.try_into() {
+                    Ok(change_type) => change_type,
+                    Err(e) => return Err(e),
                 };
-                handler(&element, change_type, Some(&runtime_id))
-            };
-            ret.map_err(|e| e.into())
-        } else {
-            Ok(())
-        }
+                if arr.is_null() {
+                    handler(&element, change_type, None)
+                } else {
+                    let runtime_id: Vec<i32> = match arr.try_into() {
+                        Ok(arr) => arr,
+                        Err(e) => return Err(e)
+                    };
+                    handler(&element, change_type, Some(&runtime_id))
+                }
+            } else {
+                Ok(())
+            }
+        })
     }
 }
 
@@ -124,13 +151,15 @@ pub struct AutomationFocusChangedEventHandler {
 
 impl IUIAutomationFocusChangedEventHandler_Impl for AutomationFocusChangedEventHandler_Impl {
     fn HandleFocusChangedEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>) -> windows_core::Result<()> {
-        if let Some(e) = sender.as_ref() {
-            let element = UIElement::from(e);
-            let handler = &self.handler;
-            handler(&element).map_err(|e| e.into())
-        } else {
-            Ok(())
-        }
+        super::handle_event(UIEventType::AutomationFocusChanged as i32, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let element = UIElement::from(e);
+                let handler = &self.handler;
+                handler(&element)
+            } else {
+                Ok(())
+            }
+        })
     }
 }
 
@@ -141,3 +170,139 @@ impl From<Box<CustomFocusChangedEventHandlerFn>> for AutomationFocusChangedEvent
         }
     }
 }
+
+#[implement(IUIAutomationNotificationEventHandler)]
+pub struct AutomationNotificationEventHandler {
+    handler: Box<CustomNotificationEventHandlerFn>
+}
+
+impl IUIAutomationNotificationEventHandler_Impl for AutomationNotificationEventHandler_Impl {
+    fn HandleNotificationEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>, notificationkind: windows::Win32::UI::Accessibility::NotificationKind, notificationprocessing: windows::Win32::UI::Accessibility::NotificationProcessing, displaystring: &BSTR, activityid: &BSTR) -> windows_core::Result<()> {
+        super::handle_event(UIEventType::Notification as i32, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let element = UIElement::from(e);
+                let kind: NotificationKind = match notificationkind.try_into() {
+                    Ok(kind) => kind,
+                    Err(e) => return Err(e)
+                };
+                let processing: NotificationProcessing = match notificationprocessing.try_into() {
+                    Ok(processing) => processing,
+                    Err(e) => return Err(e)
+                };
+                let handler = &self.handler;
+                handler(&element, kind, processing, &displaystring.to_string(), &activityid.to_string())
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+impl From<Box<CustomNotificationEventHandlerFn>> for AutomationNotificationEventHandler {
+    fn from(handler: Box<CustomNotificationEventHandlerFn>) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+#[implement(IUIAutomationChangesEventHandler)]
+pub struct AutomationChangesEventHandler {
+    handler: Box<CustomChangesEventHandlerFn>
+}
+
+impl IUIAutomationChangesEventHandler_Impl for AutomationChangesEventHandler_Impl {
+    fn HandleChangesEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>, uiachanges: *const UiaChangeInfo, changescount: i32) -> windows_core::Result<()> {
+        super::handle_event(UIEventType::Changes as i32, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let element = UIElement::from(e);
+                let changes: Vec<UIChangeInfo> = if uiachanges.is_null() || changescount <= 0 {
+                    Vec::new()
+                } else {
+                    let changes = unsafe { std::slice::from_raw_parts(uiachanges, changescount as usize) };
+                    changes.iter().map(UIChangeInfo::try_from).collect::<Result<Vec<_>>>()?
+                };
+                let handler = &self.handler;
+                handler(&element, &changes)
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+impl From<Box<CustomChangesEventHandlerFn>> for AutomationChangesEventHandler {
+    fn from(handler: Box<CustomChangesEventHandlerFn>) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+#[implement(IUIAutomationTextEditTextChangedEventHandler)]
+pub struct AutomationTextEditTextChangedEventHandler {
+    handler: Box<CustomTextEditTextChangedEventHandlerFn>
+}
+
+impl IUIAutomationTextEditTextChangedEventHandler_Impl for AutomationTextEditTextChangedEventHandler_Impl {
+    fn HandleTextEditTextChangedEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>, texteditchangetype: windows::Win32::UI::Accessibility::TextEditChangeType, eventstrings: *const windows::Win32::System::Com::SAFEARRAY) -> windows_core::Result<()> {
+        super::handle_event(UIEventType::TextEdit_TextChanged as i32, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let element = UIElement::from(e);
+                let change_type: TextEditChangeType = match texteditchangetype.try_into() {
+                    Ok(change_type) => change_type,
+                    Err(e) => return Err(e)
+                };
+                let arr = SafeArray::from(eventstrings);
+                let texts: Vec<String> = if arr.is_null() {
+                    Vec::new()
+                } else {
+                    match arr.try_into() {
+                        Ok(texts) => texts,
+                        Err(e) => return Err(e)
+                    }
+                };
+                let handler = &self.handler;
+                handler(&element, change_type, &texts)
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+impl From<Box<CustomTextEditTextChangedEventHandlerFn>> for AutomationTextEditTextChangedEventHandler {
+    fn from(handler: Box<CustomTextEditTextChangedEventHandlerFn>) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+#[implement(IUIAutomationActiveTextPositionChangedEventHandler)]
+pub struct AutomationActiveTextPositionChangedEventHandler {
+    handler: Box<CustomActiveTextPositionChangedEventHandlerFn>
+}
+
+impl IUIAutomationActiveTextPositionChangedEventHandler_Impl for AutomationActiveTextPositionChangedEventHandler_Impl {
+    fn HandleActiveTextPositionChangedEvent(&self, sender: windows_core::Ref<'_, IUIAutomationElement>, range: windows_core::Ref<'_, IUIAutomationTextRange>) -> windows_core::Result<()> {
+        super::handle_event(UIEventType::ActiveTextPositionChanged as i32, sender.as_ref(), || {
+            if let Some(e) = sender.as_ref() {
+                let element = UIElement::from(e);
+                let range = range.as_ref().map(|r| UITextRange::from(r.clone()));
+                let handler = &self.handler;
+                handler(&element, range)
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+impl From<Box<CustomActiveTextPositionChangedEventHandlerFn>> for AutomationActiveTextPositionChangedEventHandler {
+    fn from(handler: Box<CustomActiveTextPositionChangedEventHandlerFn>) -> Self {
+        Self {
+            handler
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::thread::JoinHandle;
+
+use windows::Win32::System::Com::COINIT_MULTITHREADED;
+use windows::Win32::System::Com::CoInitializeEx;
+use windows::Win32::System::Com::CoUninitialize;
+
+use crate::errors::ERR_INVALID_OBJECT;
+use crate::Error;
+use crate::Result;
+use crate::UIAutomation;
+
+use super::EventRegistration;
+use super::EventSpec;
+use super::MtaObject;
+use super::UIEvent;
+use super::sinks::guard_handler;
+
+type HubHandlerFn = dyn Fn(&UIEvent) -> Result<()> + Send;
+
+/// The requests handled by the registration thread.
+enum Command {
+    Register {
+        id: u64,
+        spec: MtaObject<EventSpec>,
+        active: Arc<AtomicBool>,
+        reply: Sender<Result<()>>
+    },
+    Remove {
+        id: u64,
+        reply: Option<Sender<Result<()>>>
+    },
+    Shutdown {
+        reply: Sender<Result<()>>
+    }
+}
+
+/// The messages handled by the dispatching thread.
+enum Dispatch {
+    Add(u64, Arc<AtomicBool>, Box<HubHandlerFn>),
+    Event(u64, UIEvent),
+    Remove(u64),
+    Stop
+}
+
+/// Owns the threads which register, remove and dispatch the event handlers.
+///
+/// The handlers are registered and removed on a dedicated thread of the multithreaded apartment,
+/// and the user callbacks run on another dedicated thread instead of the UI Automation callback threads.
+/// So the callbacks can call UI Automation, and register or remove handlers without deadlocking.
+///
+/// All the callbacks of a hub share the single dispatching thread and run one at a time in the order of the events,
+/// so a slow callback delays the events of the other handlers. Use separate hubs to isolate the slow handlers.
+/// The errors and panics of the callbacks are reported to the error sink, see `set_error_sink()`.
+///
+/// All the handlers are removed when the hub is shut down or dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::UIAutomation;
+/// use uiautomation::events::EventHub;
+/// use uiautomation::events::EventSpec;
+/// use uiautomation::events::UIEventType;
+/// use uiautomation::types::TreeScope;
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let root = automation.get_root_element().unwrap();
+///
+/// let hub = EventHub::new().unwrap();
+/// let registration = hub.add_event_handler(&EventSpec::automation(UIEventType::Window_WindowOpened, &root, TreeScope::Subtree), |event| {
+///     println!("window opened: {}", event.get_sender().get_name()?);
+///     Ok(())
+/// }).unwrap();
+///
+/// // ...
+///
+/// registration.remove().unwrap();
+/// hub.shutdown().unwrap();
+/// ```
+pub struct EventHub {
+    commands: Sender<Command>,
+    dispatcher: Sender<Dispatch>,
+    next_id: AtomicU64,
+    threads: Vec<JoinHandle<()>>
+}
+
+impl EventHub {
+    /// Starts the registration and dispatching threads, and creates a uiautomation client instance on the registration thread.
+    pub fn new() -> Result<Self> {
+        let (dispatcher, dispatch_receiver) = mpsc::channel::<Dispatch>();
+        let (commands, command_receiver) = mpsc::channel::<Command>();
+        let (init_sender, init_receiver) = mpsc::channel::<Result<()>>();
+
+        let dispatch_thread = thread::Builder::new()
+            .name("uiautomation-dispatch".into())
+            .spawn(move || Self::dispatch_loop(dispatch_receiver))
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let events = dispatcher.clone();
+        let registration_thread = thread::Builder::new()
+            .name("uiautomation-hub".into())
+            .spawn(move || {
+                let automation = match UIAutomation::new() {
+                    Ok(automation) => automation,
+                    Err(e) => {
+                        let _ = events.send(Dispatch::Stop);
+                        let _ = init_sender.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = init_sender.send(Ok(()));
+
+                Self::command_loop(&automation, command_receiver, events);
+            })
+            .map_err(|e| Error::from(e.to_string()))?;
+
+        let hub = Self {
+            commands,
+            dispatcher,
+            next_id: AtomicU64::new(1),
+            threads: vec![registration_thread, dispatch_thread]
+        };
+        init_receiver.recv().map_err(|e| Error::from(e.to_string()))??;
+
+        Ok(hub)
+    }
+
+    /// Registers `handler` for the events described by `spec`, and waits until it is registered.
+    ///
+    /// The handler runs on the dispatching thread. It is removed when the returned registration is dropped, unless it is detached.
+    /// It must be called in the multithreaded apartment, such as the threads calling `UIAutomation::new()`, since the element of `spec` is sent to the registration thread.
+    pub fn add_event_handler<F>(&self, spec: &EventSpec, handler: F) -> Result<HubRegistration> where F: Fn(&UIEvent) -> Result<()> + Send + 'static {
+        let spec = MtaObject::new(spec.clone())?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let active = Arc::new(AtomicBool::new(true));
+        self.dispatcher.send(Dispatch::Add(id, active.clone(), Box::new(handler))).map_err(|_| Self::stopped())?;
+
+        let (reply, receiver) = mpsc::channel();
+        let command = Command::Register {
+            id,
+            spec,
+            active,
+            reply
+        };
+        self.commands.send(command).map_err(|_| Self::stopped())?;
+        receiver.recv().map_err(|_| Self::stopped())??;
+
+        Ok(HubRegistration {
+            id,
+            commands: Some(self.commands.clone())
+        })
+    }
+
+    /// Removes all the event handlers, and stops the threads.
+    ///
+    /// It waits for the running callback to return, unless it is called in a callback.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop()
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        if self.threads.is_empty() {
+            return Ok(());
+        }
+
+        let (reply, receiver) = mpsc::channel();
+        let result = match self.commands.send(Command::Shutdown { reply }) {
+            Ok(_) => receiver.recv().unwrap_or(Ok(())),
+            Err(_) => Ok(())
+        };
+
+        let current = thread::current().id();
+        for handle in self.threads.drain(..) {
+            if handle.thread().id() != current {
+                let _ = handle.join();
+            }
+        }
+
+        result
+    }
+
+    fn stopped() -> Error {
+        Error::new(ERR_INVALID_OBJECT, "the event hub is shut down")
+    }
+
+    fn command_loop(automation: &UIAutomation, receiver: Receiver<Command>, dispatcher: Sender<Dispatch>) {
+        let mut registrations: HashMap<u64, (EventRegistration, Arc<AtomicBool>)> = HashMap::new();
+        let remove = |id: u64, registration: EventRegistration, active: Arc<AtomicBool>| {
+            active.store(false, Ordering::Release);
+            let result = registration.remove();
+            let _ = dispatcher.send(Dispatch::Remove(id));
+            result
+        };
+
+        while let Ok(command) = receiver.recv() {
+            match command {
+                Command::Register { id, spec, active, reply } => {
+                    let events = dispatcher.clone();
+                    let result = EventRegistration::register(automation, &spec, Box::new(move |event| {
+                        let _ = events.send(Dispatch::Event(id, event.clone()));
+                        Ok(())
+                    }));
+                    let result = match result {
+                        Ok(registration) => {
+                            registrations.insert(id, (registration, active));
+                            Ok(())
+                        },
+                        Err(e) => {
+                            active.store(false, Ordering::Release);
+                            let _ = dispatcher.send(Dispatch::Remove(id));
+                            Err(e)
+                        }
+                    };
+                    let _ = reply.send(result);
+                },
+                Command::Remove { id, reply } => {
+                    let result = match registrations.remove(&id) {
+                        Some((registration, active)) => remove(id, registration, active),
+                        None => Ok(())
+                    };
+                    if let Some(reply) = reply {
+                        let _ = reply.send(result);
+                    }
+                },
+                Command::Shutdown { reply } => {
+                    let mut result = Ok(());
+                    for (id, (registration, active)) in registrations.drain() {
+                        if let Err(e) = remove(id, registration, active) && result.is_ok() {
+                            result = Err(e);
+                        }
+                    }
+                    let _ = dispatcher.send(Dispatch::Stop);
+                    let _ = reply.send(result);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn dispatch_loop(receiver: Receiver<Dispatch>) {
+        let initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.is_ok();
+
+        let mut handlers: HashMap<u64, (Arc<AtomicBool>, Box<HubHandlerFn>)> = HashMap::new();
+        while let Ok(message) = receiver.recv() {
+            match message {
+                Dispatch::Add(id, active, handler) => {
+                    handlers.insert(id, (active, handler));
+                },
+                Dispatch::Event(id, event) => {
+                    if let Some((active, handler)) = handlers.get(&id) && active.load(Ordering::Acquire) {
+                        let _ = guard_handler(event.get_event_type() as i32, || Some(event.get_sender().clone()), || handler(&event));
+                    }
+                },
+                Dispatch::Remove(id) => {
+                    handlers.remove(&id);
+                },
+                Dispatch::Stop => break
+            }
+        }
+
+        // releases the pending events and the handlers before uninitializing COM.
+        drop(handlers);
+        drop(receiver);
+        if initialized {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
+
+impl Drop for EventHub {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+impl std::fmt::Debug for EventHub {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventHub").field("running", &!self.threads.is_empty()).finish()
+    }
+}
+
+/// A guard of an event handler registered by `EventHub::add_event_handler()`.
+///
+/// Dropping the guard requests the removal without waiting, so it can be dropped anywhere, including the handler itself.
+/// The removal is ignored after the hub is shut down, as all its handlers are already removed.
+#[must_use = "the handler is removed when the registration is dropped"]
+#[derive(Debug)]
+pub struct HubRegistration {
+    id: u64,
+    commands: Option<Sender<Command>>
+}
+
+impl HubRegistration {
+    /// Removes the event handler, and waits until it is removed.
+    ///
+    /// The handler is not called any more after it returns, except the callback which is running now.
+    pub fn remove(mut self) -> Result<()> {
+        let Some(commands) = self.commands.take() else {
+            return Ok(());
+        };
+
+        let (reply, receiver) = mpsc::channel();
+        if commands.send(Command::Remove { id: self.id, reply: Some(reply) }).is_err() {
+            return Ok(());
+        }
+        receiver.recv().unwrap_or(Ok(()))
+    }
+
+    /// Keeps the event handler registered until the hub is shut down.
+    pub fn detach(mut self) {
+        self.commands = None;
+    }
+}
+
+impl Drop for HubRegistration {
+    fn drop(&mut self) {
+        if let Some(commands) = self.commands.take() {
+            let _ = commands.send(Command::Remove { id: self.id, reply: None });
+        }
+    }
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Register { id, .. } => f.debug_struct("Register").field("id", id).finish(),
+            Self::Remove { id, .. } => f.debug_struct("Remove").field("id", id).finish(),
+            Self::Shutdown { .. } => f.write_str("Shutdown")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use chrono::Local;
+
+    use crate::UIAutomation;
+    use crate::events::EventSpec;
+    use crate::events::UIEvent;
+    use crate::events::UIEventType;
+    use crate::types::TreeScope;
+
+    use super::Dispatch;
+    use super::EventHub;
+    use super::MtaObject;
+    use super::HubRegistration;
+
+    #[test]
+    fn test_event_hub() {
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+        // the desktop is never invoked, so only the events sent by the test are dispatched.
+        let spec = EventSpec::automation(UIEventType::Invoke_Invoked, &root, TreeScope::Element);
+        let event = UIEvent::Automation { sender: MtaObject::new(root.clone()).unwrap(), timestamp: Local::now(), event_type: UIEventType::Invoke_Invoked };
+
+        let hub = EventHub::new().unwrap();
+        let (sender, receiver) = mpsc::channel();
+
+        // removes the handler from its own callback, which does not deadlock.
+        let holder: Arc<Mutex<Option<HubRegistration>>> = Arc::new(Mutex::new(None));
+        let callback_holder = holder.clone();
+        let removed_sender = sender.clone();
+        let removed = hub.add_event_handler(&spec, move |_| {
+            removed_sender.send("removed").unwrap();
+            callback_holder.lock().unwrap().take();
+            Ok(())
+        }).unwrap();
+        let removed_id = removed.id;
+        *holder.lock().unwrap() = Some(removed);
+
+        let probe_sender = sender.clone();
+        let probe = hub.add_event_handler(&spec, move |_| {
+            probe_sender.send("probe").unwrap();
+            Ok(())
+        }).unwrap();
+        let probe_id = probe.id;
+        probe.detach();
+        drop(sender);
+
+        let dispatcher = hub.dispatcher.clone();
+        dispatcher.send(Dispatch::Event(removed_id, event.clone())).unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok("removed"));
+
+        // the removal requested in the callback is done before the later commands.
+        hub.add_event_handler(&spec, |_| Ok(())).unwrap().remove().unwrap();
+        dispatcher.send(Dispatch::Event(removed_id, event.clone())).unwrap();
+        dispatcher.send(Dispatch::Event(probe_id, event.clone())).unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok("probe"));
+        assert!(holder.lock().unwrap().is_none());
+
+        println!("{:?}", hub);
+        hub.shutdown().unwrap();
+
+        // the dispatching thread is stopped, and the handlers are released.
+        let _ = dispatcher.send(Dispatch::Event(probe_id, event));
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Err(mpsc::RecvTimeoutError::Disconnected));
+    }
+}
@@ -0,0 +1,506 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use windows::Win32::System::Com::COINIT_MULTITHREADED;
+use windows::Win32::System::Com::CoInitializeEx;
+use windows::Win32::System::Com::CoUninitialize;
+
+use crate::types::StructureChangeType;
+use crate::Error;
+use crate::Result;
+
+use super::UIEvent;
+use super::sinks::guard_handler;
+
+/// Describes the parts of an event used by the middlewares.
+///
+/// It is implemented by `UIEvent`, and can be implemented by plain data types to test the middlewares without UI Automation.
+pub trait EventInfo: Clone {
+    /// Retrieves the runtime id of the sender, which identifies the sender.
+    fn get_sender_id(&self) -> Vec<i32>;
+
+    /// Retrieves the id of the event, such as the event type id or the property id.
+    fn get_event_id(&self) -> i32;
+
+    /// Checks whether the event is a structure-changed event.
+    fn is_structure_changed(&self) -> bool;
+
+    /// Creates the "subtree changed" event which replaces the structure-changed events in `events`.
+    ///
+    /// The `events` are not empty, and have the same sender id.
+    fn coalesce(events: Vec<Self>) -> Self;
+}
+
+impl EventInfo for UIEvent {
+    fn get_sender_id(&self) -> Vec<i32> {
+        self.get_sender().get_runtime_id().unwrap_or_default()
+    }
+
+    fn get_event_id(&self) -> i32 {
+        match self {
+            Self::PropertyChanged { property, .. } => *property as i32,
+            _ => self.get_event_type() as i32
+        }
+    }
+
+    fn is_structure_changed(&self) -> bool {
+        matches!(self, Self::StructureChanged { .. })
+    }
+
+    /// Creates a `ChildrenInvalidated` event of the common sender, at the time of the first event.
+    fn coalesce(events: Vec<Self>) -> Self {
+        let first = events.into_iter().next().expect("no event to coalesce");
+        Self::StructureChanged {
+            sender: first.get_sender_object().clone(),
+            timestamp: first.get_timestamp(),
+            change_type: StructureChangeType::ChildrenInvalidated,
+            runtime_id: None
+        }
+    }
+}
+
+/// Defines a stage of an `EventPipeline`.
+///
+/// The current time is passed in by the caller, so the stages do not read the clock themselves.
+pub trait EventMiddleware<E>: Send {
+    /// Processes an `event` received at `now`, and appends the events to pass on to `output`.
+    fn process(&mut self, event: E, now: Instant, output: &mut Vec<E>);
+
+    /// Appends the delayed events which are due at `now` to `output`.
+    fn flush(&mut self, _now: Instant, _output: &mut Vec<E>) {}
+
+    /// Retrieves the time when the next delayed event is due.
+    fn get_deadline(&self) -> Option<Instant> {
+        None
+    }
+}
+
+/// Passes the events which satisfy the predicate.
+pub struct FilterMiddleware<F> {
+    predicate: F
+}
+
+impl<F> FilterMiddleware<F> {
+    pub fn new(predicate: F) -> Self {
+        Self {
+            predicate
+        }
+    }
+}
+
+impl<E, F> EventMiddleware<E> for FilterMiddleware<F> where F: FnMut(&E) -> bool + Send {
+    fn process(&mut self, event: E, _now: Instant, output: &mut Vec<E>) {
+        if (self.predicate)(&event) {
+            output.push(event);
+        }
+    }
+}
+
+/// Passes the last event of each (sender, event id) after no more such events are received for `quiet` time.
+pub struct DebounceMiddleware<E> {
+    quiet: Duration,
+    pending: HashMap<(Vec<i32>, i32), (Instant, E)>
+}
+
+impl<E> DebounceMiddleware<E> {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            pending: HashMap::new()
+        }
+    }
+}
+
+impl<E: EventInfo + Send> EventMiddleware<E> for DebounceMiddleware<E> {
+    fn process(&mut self, event: E, now: Instant, _output: &mut Vec<E>) {
+        let key = (event.get_sender_id(), event.get_event_id());
+        self.pending.insert(key, (now + self.quiet, event));
+    }
+
+    fn flush(&mut self, now: Instant, output: &mut Vec<E>) {
+        let mut due: Vec<(Instant, E)> = Vec::new();
+        self.pending.retain(|_, (deadline, event)| {
+            if *deadline <= now {
+                due.push((*deadline, event.clone()));
+                false
+            } else {
+                true
+            }
+        });
+        due.sort_by_key(|(deadline, _)| *deadline);
+        output.extend(due.into_iter().map(|(_, event)| event));
+    }
+
+    fn get_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|(deadline, _)| *deadline).min()
+    }
+}
+
+/// Coalesces the structure-changed events of each sender received within `window` time into one "subtree changed" event,
+/// created by `EventInfo::coalesce()`.
+///
+/// The other events are passed on immediately.
+pub struct CoalesceMiddleware<E> {
+    window: Duration,
+    pending: HashMap<Vec<i32>, (Instant, Vec<E>)>
+}
+
+impl<E> CoalesceMiddleware<E> {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new()
+        }
+    }
+}
+
+impl<E: EventInfo + Send> EventMiddleware<E> for CoalesceMiddleware<E> {
+    fn process(&mut self, event: E, now: Instant, output: &mut Vec<E>) {
+        if event.is_structure_changed() {
+            let (_, events) = self.pending.entry(event.get_sender_id()).or_insert_with(|| (now + self.window, Vec::new()));
+            events.push(event);
+        } else {
+            output.push(event);
+        }
+    }
+
+    fn flush(&mut self, now: Instant, output: &mut Vec<E>) {
+        let mut due: Vec<(Instant, Vec<E>)> = Vec::new();
+        self.pending.retain(|_, (deadline, events)| {
+            if *deadline <= now {
+                due.push((*deadline, std::mem::take(events)));
+                false
+            } else {
+                true
+            }
+        });
+        due.sort_by_key(|(deadline, _)| *deadline);
+        output.extend(due.into_iter().map(|(_, events)| E::coalesce(events)));
+    }
+
+    fn get_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|(deadline, _)| *deadline).min()
+    }
+}
+
+/// Passes at most `limit` events in any `period` of time, and drops the others.
+pub struct RateLimitMiddleware {
+    limit: usize,
+    period: Duration,
+    passed: VecDeque<Instant>
+}
+
+impl RateLimitMiddleware {
+    pub fn new(limit: usize, period: Duration) -> Self {
+        Self {
+            limit,
+            period,
+            passed: VecDeque::new()
+        }
+    }
+}
+
+impl<E> EventMiddleware<E> for RateLimitMiddleware {
+    fn process(&mut self, event: E, now: Instant, output: &mut Vec<E>) {
+        while self.passed.front().is_some_and(|t| now.saturating_duration_since(*t) >= self.period) {
+            self.passed.pop_front();
+        }
+
+        if self.passed.len() < self.limit {
+            self.passed.push_back(now);
+            output.push(event);
+        }
+    }
+}
+
+/// A chain of event middlewares, such as filtering, debouncing, coalescing and rate limiting.
+///
+/// The pipeline itself does not depend on UI Automation. Call `into_handler()` to use it with `UIAutomation::add_event_handler()`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use uiautomation::UIAutomation;
+/// use uiautomation::events::EventPipeline;
+/// use uiautomation::events::EventSpec;
+/// use uiautomation::events::UIEvent;
+/// use uiautomation::types::TreeScope;
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let root = automation.get_root_element().unwrap();
+///
+/// let handler = EventPipeline::<UIEvent>::new()
+///     .filter(|event: &UIEvent| event.get_sender().get_process_id().unwrap_or_default() != std::process::id())
+///     .coalesce_structure_changes(Duration::from_millis(200))
+///     .rate_limit(10, Duration::from_secs(1))
+///     .into_handler(|event| {
+///         println!("{:?}", event);
+///         Ok(())
+///     });
+/// let registration = automation.add_event_handler(&EventSpec::structure_changed(&root, TreeScope::Subtree), handler).unwrap();
+/// ```
+pub struct EventPipeline<E> {
+    stages: Vec<Box<dyn EventMiddleware<E>>>
+}
+
+impl<E: 'static> EventPipeline<E> {
+    /// Creates an empty pipeline, which passes all the events.
+    pub fn new() -> Self {
+        Self {
+            stages: Vec::new()
+        }
+    }
+
+    /// Appends a custom stage.
+    pub fn stage<M>(mut self, middleware: M) -> Self where M: EventMiddleware<E> + 'static {
+        self.stages.push(Box::new(middleware));
+        self
+    }
+
+    /// Appends a stage which passes the events satisfying `predicate`.
+    pub fn filter<F>(self, predicate: F) -> Self where F: FnMut(&E) -> bool + Send + 'static {
+        self.stage(FilterMiddleware::new(predicate))
+    }
+
+    /// Appends a stage which rate limits the events to `limit` per `period`.
+    pub fn rate_limit(self, limit: usize, period: Duration) -> Self {
+        self.stage(RateLimitMiddleware::new(limit, period))
+    }
+
+    /// Processes an `event` received at `now`, and returns the events passing all the stages.
+    pub fn process(&mut self, event: E, now: Instant) -> Vec<E> {
+        self.run_from(0, vec![event], now)
+    }
+
+    /// Flushes the delayed events which are due at `now` through the following stages.
+    pub fn flush(&mut self, now: Instant) -> Vec<E> {
+        let mut output = Vec::new();
+        for index in 0..self.stages.len() {
+            let mut flushed = Vec::new();
+            self.stages[index].flush(now, &mut flushed);
+            output.extend(self.run_from(index + 1, flushed, now));
+        }
+        output
+    }
+
+    /// Flushes all the delayed events in the order of their deadlines, as if the deadlines passed.
+    pub fn flush_all(&mut self) -> Vec<E> {
+        let mut output = Vec::new();
+        while let Some(deadline) = self.get_deadline() {
+            output.extend(self.flush(deadline));
+        }
+        output
+    }
+
+    /// Retrieves the time when the next delayed event is due.
+    pub fn get_deadline(&self) -> Option<Instant> {
+        self.stages.iter().filter_map(|stage| stage.get_deadline()).min()
+    }
+
+    fn run_from(&mut self, start: usize, mut events: Vec<E>, now: Instant) -> Vec<E> {
+        for stage in self.stages.iter_mut().skip(start) {
+            if events.is_empty() {
+                break;
+            }
+
+            let mut output = Vec::with_capacity(events.len());
+            for event in events {
+                stage.process(event, now, &mut output);
+            }
+            events = output;
+        }
+        events
+    }
+}
+
+impl<E: EventInfo + Send + 'static> EventPipeline<E> {
+    /// Appends a stage which debounces the events of each (sender, event id) by `quiet` time.
+    pub fn debounce(self, quiet: Duration) -> Self {
+        self.stage(DebounceMiddleware::new(quiet))
+    }
+
+    /// Appends a stage which coalesces the structure-changed events within `window` time.
+    pub fn coalesce_structure_changes(self, window: Duration) -> Self {
+        self.stage(CoalesceMiddleware::new(window))
+    }
+}
+
+impl<E: 'static> Default for EventPipeline<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventPipeline<UIEvent> {
+    /// Converts the pipeline to an event handler, which passes the processed events to `handler`.
+    ///
+    /// The pipeline and `handler` run on a dedicated thread of the multithreaded apartment, which stops after the returned handler is dropped.
+    /// The delayed events are flushed to `handler` before the thread stops.
+    pub fn into_handler<F>(self, handler: F) -> impl Fn(&UIEvent) -> Result<()> + 'static where F: Fn(&UIEvent) -> Result<()> + Send + 'static {
+        let (sender, receiver) = mpsc::channel::<(UIEvent, Instant)>();
+        let mut pipeline = self;
+        let _ = thread::Builder::new().name("uiautomation-events".into()).spawn(move || {
+            let initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.is_ok();
+            loop {
+                let received = match pipeline.get_deadline() {
+                    Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                };
+
+                let (events, disconnected) = match received {
+                    Ok((event, received_at)) => {
+                        let mut events = pipeline.process(event, received_at);
+                        events.extend(pipeline.flush(Instant::now()));
+                        (events, false)
+                    },
+                    Err(RecvTimeoutError::Timeout) => (pipeline.flush(Instant::now()), false),
+                    Err(RecvTimeoutError::Disconnected) => (pipeline.flush_all(), true)
+                };
+                for event in events.iter() {
+                    let _ = guard_handler(event.get_event_type() as i32, || Some(event.get_sender().clone()), || handler(event));
+                }
+                if disconnected {
+                    break;
+                }
+            }
+            drop(pipeline);
+            if initialized {
+                unsafe { CoUninitialize() };
+            }
+        });
+
+        move |event: &UIEvent| {
+            sender.send((event.clone(), Instant::now())).map_err(|e| Error::from(e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use super::EventInfo;
+    use super::EventPipeline;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestEvent {
+        sender: i32,
+        id: i32,
+        count: usize
+    }
+
+    const STRUCTURE_CHANGED: i32 = 20002;
+
+    impl EventInfo for TestEvent {
+        fn get_sender_id(&self) -> Vec<i32> {
+            vec![self.sender]
+        }
+
+        fn get_event_id(&self) -> i32 {
+            self.id
+        }
+
+        fn is_structure_changed(&self) -> bool {
+            self.id == STRUCTURE_CHANGED
+        }
+
+        fn coalesce(events: Vec<Self>) -> Self {
+            TestEvent { sender: events[0].sender, id: STRUCTURE_CHANGED, count: events.len() }
+        }
+    }
+
+    fn event(sender: i32, id: i32) -> TestEvent {
+        TestEvent { sender, id, count: 1 }
+    }
+
+    fn ms(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_filter_and_rate_limit() {
+        let start = Instant::now();
+        let mut pipeline = EventPipeline::new()
+            .filter(|e: &TestEvent| e.sender != 0)
+            .rate_limit(2, Duration::from_millis(100));
+
+        assert!(pipeline.process(event(0, 1), start).is_empty());
+        assert_eq!(pipeline.process(event(1, 1), ms(start, 0)).len(), 1);
+        assert_eq!(pipeline.process(event(1, 1), ms(start, 10)).len(), 1);
+        assert!(pipeline.process(event(1, 1), ms(start, 20)).is_empty());
+        assert_eq!(pipeline.process(event(1, 1), ms(start, 100)).len(), 1);
+        assert!(pipeline.get_deadline().is_none());
+    }
+
+    #[test]
+    fn test_debounce() {
+        let start = Instant::now();
+        let mut pipeline = EventPipeline::new().debounce(Duration::from_millis(50));
+
+        assert!(pipeline.process(event(1, 30005), ms(start, 0)).is_empty());
+        assert!(pipeline.process(event(1, 30005), ms(start, 30)).is_empty());
+        assert!(pipeline.process(event(2, 30005), ms(start, 40)).is_empty());
+        assert_eq!(pipeline.get_deadline(), Some(ms(start, 80)));
+
+        assert!(pipeline.flush(ms(start, 79)).is_empty());
+        assert_eq!(pipeline.flush(ms(start, 80)), vec![event(1, 30005)]);
+        assert_eq!(pipeline.flush(ms(start, 90)), vec![event(2, 30005)]);
+        assert!(pipeline.get_deadline().is_none());
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let start = Instant::now();
+        let mut pipeline = EventPipeline::new()
+            .coalesce_structure_changes(Duration::from_millis(100))
+            .rate_limit(1, Duration::from_secs(1));
+
+        assert!(pipeline.process(event(1, STRUCTURE_CHANGED), ms(start, 0)).is_empty());
+        assert!(pipeline.process(event(1, STRUCTURE_CHANGED), ms(start, 10)).is_empty());
+        assert!(pipeline.process(event(1, STRUCTURE_CHANGED), ms(start, 20)).is_empty());
+
+        let flushed = pipeline.flush(ms(start, 100));
+        assert_eq!(flushed, vec![TestEvent { sender: 1, id: STRUCTURE_CHANGED, count: 3 }]);
+
+        assert!(pipeline.process(event(1, 20016), ms(start, 110)).is_empty());
+        assert!(pipeline.flush(ms(start, 200)).is_empty());
+    }
+
+    #[test]
+    fn test_coalesce_senders() {
+        let start = Instant::now();
+        let mut pipeline = EventPipeline::new().coalesce_structure_changes(Duration::from_millis(100));
+
+        assert!(pipeline.process(event(1, STRUCTURE_CHANGED), ms(start, 0)).is_empty());
+        assert!(pipeline.process(event(2, STRUCTURE_CHANGED), ms(start, 10)).is_empty());
+        assert!(pipeline.process(event(1, STRUCTURE_CHANGED), ms(start, 20)).is_empty());
+        assert_eq!(pipeline.get_deadline(), Some(ms(start, 100)));
+
+        assert_eq!(pipeline.flush(ms(start, 100)), vec![TestEvent { sender: 1, id: STRUCTURE_CHANGED, count: 2 }]);
+        assert_eq!(pipeline.flush(ms(start, 110)), vec![TestEvent { sender: 2, id: STRUCTURE_CHANGED, count: 1 }]);
+        assert!(pipeline.get_deadline().is_none());
+    }
+
+    #[test]
+    fn test_flush_all() {
+        let start = Instant::now();
+        let mut pipeline = EventPipeline::new()
+            .coalesce_structure_changes(Duration::from_millis(100))
+            .debounce(Duration::from_millis(50));
+
+        assert!(pipeline.process(event(1, STRUCTURE_CHANGED), ms(start, 0)).is_empty());
+        assert!(pipeline.process(event(2, 30005), ms(start, 10)).is_empty());
+
+        let flushed = pipeline.flush_all();
+        assert_eq!(flushed, vec![event(2, 30005), TestEvent { sender: 1, id: STRUCTURE_CHANGED, count: 1 }]);
+        assert!(pipeline.get_deadline().is_none());
+    }
+}
@@ -1,18 +1,83 @@
 mod handlers;
 mod functions;
-
+mod hubs;
+mod registrations;
+mod middlewares;
+mod objects;
+mod records;
+mod sinks;
+mod subscriptions;
+#[cfg(feature = "async")]
+mod streams;
+#[cfg(feature = "process")]
+mod trackers;
+
+pub use self::hubs::EventHub;
+pub use self::hubs::HubRegistration;
+pub use self::middlewares::CoalesceMiddleware;
+pub use self::middlewares::DebounceMiddleware;
+pub use self::middlewares::EventInfo;
+pub use self::middlewares::EventMiddleware;
+pub use self::middlewares::EventPipeline;
+pub use self::middlewares::FilterMiddleware;
+pub use self::middlewares::RateLimitMiddleware;
+pub use self::objects::MtaObject;
+pub use self::registrations::EventRegistration;
+pub use self::registrations::EventSpec;
+pub use self::registrations::UIEventHandlerFn;
+pub(crate) use self::registrations::Registration;
+pub use self::records::EventRecorder;
+pub use self::records::EventReplayer;
+pub use self::records::RecordedChange;
+pub use self::records::RecordedEvent;
+pub use self::records::RecordedPayload;
+pub use self::records::ReplaySummary;
+pub use self::sinks::ErrorSinkFn;
+pub use self::sinks::HandlerError;
+pub use self::sinks::clear_error_sink;
+pub use self::sinks::set_error_channel;
+pub use self::sinks::set_error_sink;
+pub use self::subscriptions::Subscription;
+#[cfg(feature = "async")]
+pub use self::streams::EventStream;
+#[cfg(feature = "async")]
+pub use self::streams::OverflowPolicy;
+#[cfg(feature = "process")]
+pub use self::trackers::TrackedWindow;
+#[cfg(feature = "process")]
+pub use self::trackers::WindowTracker;
+
+use std::cell::Cell;
+use std::fmt::Debug;
+
+use chrono::DateTime;
+use chrono::Local;
 use uiautomation_derive::map_as;
 use uiautomation_derive::EnumConvert;
+use windows::Win32::UI::Accessibility::IUIAutomationActiveTextPositionChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationChangesEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationElement;
 use windows::Win32::UI::Accessibility::IUIAutomationEventHandler;
 use windows::Win32::UI::Accessibility::IUIAutomationFocusChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationNotificationEventHandler;
 use windows::Win32::UI::Accessibility::IUIAutomationPropertyChangedEventHandler;
 use windows::Win32::UI::Accessibility::IUIAutomationStructureChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationTextEditTextChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationTextRange;
+use windows::Win32::UI::Accessibility::UiaChangeInfo;
+use windows::core::BSTR;
 use windows_core::Param;
 
+use crate::patterns::UITextRange;
+use crate::types::NotificationKind;
+use crate::types::NotificationProcessing;
 use crate::types::StructureChangeType;
+use crate::types::TextEditChangeType;
 use crate::types::UIProperty;
 use crate::variants::SafeArray;
 use crate::variants::Variant;
+use crate::variants::Value;
+use crate::Error;
 use crate::Result;
 use crate::UIElement;
 
@@ -122,6 +187,188 @@ pub enum UIEventType {
     ActiveTextPositionChanged = 20036i32,
 }
 
+thread_local! {
+    static HANDLING_EVENTS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Runs `f` as an event handler callback of the event `event_id` on current thread.
+///
+/// The panic of `f` is caught instead of unwinding across the COM boundary, and the failure is reported to the error sink.
+pub(crate) fn handle_event(event_id: i32, sender: Option<&IUIAutomationElement>, f: impl FnOnce() -> Result<()>) -> windows::core::Result<()> {
+    HANDLING_EVENTS.with(|count| count.set(count.get() + 1));
+    let ret = self::sinks::guard_handler(event_id, || sender.map(UIElement::from), f);
+    HANDLING_EVENTS.with(|count| count.set(count.get() - 1));
+    ret.map_err(|e| e.into())
+}
+
+/// Checks whether current thread is running an event handler callback.
+pub(crate) fn is_handling_event() -> bool {
+    HANDLING_EVENTS.with(|count| count.get() > 0)
+}
+
+/// The information of a change, wrapping `windows::Win32::UI::Accessibility::UiaChangeInfo`.
+#[derive(Clone)]
+pub struct UIChangeInfo {
+    /// The identifier of the change type, such as an annotation type or a style id.
+    pub uia_id: i32,
+    /// A copy of the data of the change.
+    pub payload: Value,
+    /// A copy of the extra information of the change.
+    pub extra_info: Value
+}
+
+impl TryFrom<&UiaChangeInfo> for UIChangeInfo {
+    type Error = Error;
+
+    fn try_from(value: &UiaChangeInfo) -> Result<Self> {
+        Ok(Self {
+            uia_id: value.uiaId,
+            payload: Variant::from(&value.payload).get_owned_value()?,
+            extra_info: Variant::from(&value.extraInfo).get_owned_value()?
+        })
+    }
+}
+
+impl From<UIChangeInfo> for UiaChangeInfo {
+    fn from(value: UIChangeInfo) -> Self {
+        Self {
+            uiaId: value.uia_id,
+            payload: Variant::from(value.payload).into(),
+            extraInfo: Variant::from(value.extra_info).into()
+        }
+    }
+}
+
+impl Debug for UIChangeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UIChangeInfo").field("uia_id", &self.uia_id).field("payload", &self.payload.to_string()).field("extra_info", &self.extra_info.to_string()).finish()
+    }
+}
+
+/// An event of any kind, carrying the sender element, the time it is received and the kind-specific payload.
+///
+/// The UI Automation objects of the event are held by `MtaObject`, so the event can be sent to other threads.
+#[derive(Clone)]
+pub enum UIEvent {
+    /// A Microsoft UI Automation event, such as `Window_WindowOpened`.
+    Automation {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>,
+        event_type: UIEventType
+    },
+    /// A property-changed event with a copy of the new value of the property.
+    PropertyChanged {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>,
+        property: UIProperty,
+        value: MtaObject<Value>
+    },
+    /// A structure-changed event with the runtime id of the changed child, if it is provided.
+    StructureChanged {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>,
+        change_type: StructureChangeType,
+        runtime_id: Option<Vec<i32>>
+    },
+    /// A focus-changed event, whose sender is the element getting the focus.
+    FocusChanged {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>
+    },
+    /// A notification event, such as a toast-like message raised by an application.
+    Notification {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>,
+        kind: NotificationKind,
+        processing: NotificationProcessing,
+        display: String,
+        activity_id: String
+    },
+    /// A changes event, carrying one or more changes.
+    Changes {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>,
+        changes: MtaObject<Vec<UIChangeInfo>>
+    },
+    /// A text-changed event raised by a text edit control.
+    TextEditTextChanged {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>,
+        change_type: TextEditChangeType,
+        texts: Vec<String>
+    },
+    /// An active-text-position-changed event, with the text range of the new position if it is provided.
+    ActiveTextPositionChanged {
+        sender: MtaObject<UIElement>,
+        timestamp: DateTime<Local>,
+        range: Option<MtaObject<UITextRange>>
+    }
+}
+
+impl UIEvent {
+    /// Retrieves the element which raises the event.
+    pub fn get_sender(&self) -> &UIElement {
+        self.get_sender_object()
+    }
+
+    /// Retrieves the element which raises the event, as it is held by the event.
+    pub(crate) fn get_sender_object(&self) -> &MtaObject<UIElement> {
+        match self {
+            Self::Automation { sender, .. } => sender,
+            Self::PropertyChanged { sender, .. } => sender,
+            Self::StructureChanged { sender, .. } => sender,
+            Self::FocusChanged { sender, .. } => sender,
+            Self::Notification { sender, .. } => sender,
+            Self::Changes { sender, .. } => sender,
+            Self::TextEditTextChanged { sender, .. } => sender,
+            Self::ActiveTextPositionChanged { sender, .. } => sender
+        }
+    }
+
+    /// Retrieves the time when the event is received.
+    pub fn get_timestamp(&self) -> DateTime<Local> {
+        match self {
+            Self::Automation { timestamp, .. } => *timestamp,
+            Self::PropertyChanged { timestamp, .. } => *timestamp,
+            Self::StructureChanged { timestamp, .. } => *timestamp,
+            Self::FocusChanged { timestamp, .. } => *timestamp,
+            Self::Notification { timestamp, .. } => *timestamp,
+            Self::Changes { timestamp, .. } => *timestamp,
+            Self::TextEditTextChanged { timestamp, .. } => *timestamp,
+            Self::ActiveTextPositionChanged { timestamp, .. } => *timestamp
+        }
+    }
+
+    /// Retrieves the type of the event.
+    pub fn get_event_type(&self) -> UIEventType {
+        match self {
+            Self::Automation { event_type, .. } => *event_type,
+            Self::PropertyChanged { .. } => UIEventType::AutomationPropertyChanged,
+            Self::StructureChanged { .. } => UIEventType::StructureChanged,
+            Self::FocusChanged { .. } => UIEventType::AutomationFocusChanged,
+            Self::Notification { .. } => UIEventType::Notification,
+            Self::Changes { .. } => UIEventType::Changes,
+            Self::TextEditTextChanged { .. } => UIEventType::TextEdit_TextChanged,
+            Self::ActiveTextPositionChanged { .. } => UIEventType::ActiveTextPositionChanged
+        }
+    }
+}
+
+impl Debug for UIEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Automation { sender, timestamp, event_type } => f.debug_struct("Automation").field("sender", sender).field("timestamp", timestamp).field("event_type", event_type).finish(),
+            Self::PropertyChanged { sender, timestamp, property, value } => f.debug_struct("PropertyChanged").field("sender", sender).field("timestamp", timestamp).field("property", property).field("value", &value.to_string()).finish(),
+            Self::StructureChanged { sender, timestamp, change_type, runtime_id } => f.debug_struct("StructureChanged").field("sender", sender).field("timestamp", timestamp).field("change_type", change_type).field("runtime_id", runtime_id).finish(),
+            Self::FocusChanged { sender, timestamp } => f.debug_struct("FocusChanged").field("sender", sender).field("timestamp", timestamp).finish(),
+            Self::Notification { sender, timestamp, kind, processing, display, activity_id } => f.debug_struct("Notification").field("sender", sender).field("timestamp", timestamp).field("kind", kind).field("processing", processing).field("display", display).field("activity_id", activity_id).finish(),
+            Self::Changes { sender, timestamp, changes } => f.debug_struct("Changes").field("sender", sender).field("timestamp", timestamp).field("changes", changes).finish(),
+            Self::TextEditTextChanged { sender, timestamp, change_type, texts } => f.debug_struct("TextEditTextChanged").field("sender", sender).field("timestamp", timestamp).field("change_type", change_type).field("texts", texts).finish(),
+            Self::ActiveTextPositionChanged { sender, timestamp, range } => f.debug_struct("ActiveTextPositionChanged").field("sender", sender).field("timestamp", timestamp).field("range", range).finish()
+        }
+    }
+}
+
 /// A wrapper for windows `IUIAutomationEventHandler` interface. 
 /// 
 /// Exposes a method to handle Microsoft UI Automation events.
@@ -353,6 +600,233 @@ impl Param<IUIAutomationFocusChangedEventHandler> for &UIFocusChangedEventHandle
     }}
 }
 
+/// A wrapper for windows `IUIAutomationNotificationEventHandler` interface. 
+/// 
+/// Exposes a method to handle Microsoft UI Automation notification events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UINotificationEventHandler {
+    handler: IUIAutomationNotificationEventHandler
+}
+
+impl UINotificationEventHandler {
+    /// Handles a Microsoft UI Automation notification event.
+    pub fn handle_notification_event(&self, sender: &UIElement, kind: NotificationKind, processing: NotificationProcessing, display: &str, activity_id: &str) -> Result<()> {
+        unsafe {
+            self.handler.HandleNotificationEvent(sender, kind.into(), processing.into(), &BSTR::from(display), &BSTR::from(activity_id))?
+        };
+        Ok(())
+    }
+}
+
+impl From<IUIAutomationNotificationEventHandler> for UINotificationEventHandler {
+    fn from(handler: IUIAutomationNotificationEventHandler) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+impl From<&IUIAutomationNotificationEventHandler> for UINotificationEventHandler {
+    fn from(value: &IUIAutomationNotificationEventHandler) -> Self {
+        value.clone().into()
+    }
+}
+
+impl From<UINotificationEventHandler> for IUIAutomationNotificationEventHandler {
+    fn from(value: UINotificationEventHandler) -> Self {
+        value.handler
+    }
+}
+
+impl AsRef<IUIAutomationNotificationEventHandler> for UINotificationEventHandler {
+    fn as_ref(&self) -> &IUIAutomationNotificationEventHandler {
+        &self.handler
+    }
+}
+
+impl Param<IUIAutomationNotificationEventHandler> for UINotificationEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationNotificationEventHandler> { unsafe {
+        self.handler.param()
+    }}
+}
+
+impl Param<IUIAutomationNotificationEventHandler> for &UINotificationEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationNotificationEventHandler> { unsafe {
+        (&self.handler).param()
+    }}
+}
+
+/// A wrapper for windows `IUIAutomationChangesEventHandler` interface. 
+/// 
+/// Exposes a method to handle one or more Microsoft UI Automation change events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UIChangesEventHandler {
+    handler: IUIAutomationChangesEventHandler
+}
+
+impl UIChangesEventHandler {
+    /// Handles one or more Microsoft UI Automation change events.
+    pub fn handle_changes_event(&self, sender: &UIElement, changes: &[UIChangeInfo]) -> Result<()> {
+        let changes: Vec<UiaChangeInfo> = changes.iter().map(|c| c.clone().into()).collect();
+        unsafe {
+            self.handler.HandleChangesEvent(sender, &changes)?
+        };
+        Ok(())
+    }
+}
+
+impl From<IUIAutomationChangesEventHandler> for UIChangesEventHandler {
+    fn from(handler: IUIAutomationChangesEventHandler) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+impl From<&IUIAutomationChangesEventHandler> for UIChangesEventHandler {
+    fn from(value: &IUIAutomationChangesEventHandler) -> Self {
+        value.clone().into()
+    }
+}
+
+impl From<UIChangesEventHandler> for IUIAutomationChangesEventHandler {
+    fn from(value: UIChangesEventHandler) -> Self {
+        value.handler
+    }
+}
+
+impl AsRef<IUIAutomationChangesEventHandler> for UIChangesEventHandler {
+    fn as_ref(&self) -> &IUIAutomationChangesEventHandler {
+        &self.handler
+    }
+}
+
+impl Param<IUIAutomationChangesEventHandler> for UIChangesEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationChangesEventHandler> { unsafe {
+        self.handler.param()
+    }}
+}
+
+impl Param<IUIAutomationChangesEventHandler> for &UIChangesEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationChangesEventHandler> { unsafe {
+        (&self.handler).param()
+    }}
+}
+
+/// A wrapper for windows `IUIAutomationTextEditTextChangedEventHandler` interface. 
+/// 
+/// Exposes a method to handle events that occur when Microsoft UI Automation reports a text-changed event from text edit controls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UITextEditTextChangedEventHandler {
+    handler: IUIAutomationTextEditTextChangedEventHandler
+}
+
+impl UITextEditTextChangedEventHandler {
+    /// Handles an event that is raised when a text edit control changes the text.
+    pub fn handle_text_edit_text_changed_event(&self, sender: &UIElement, change_type: TextEditChangeType, texts: &[String]) -> Result<()> {
+        let texts: SafeArray = texts.to_vec().try_into()?;
+        unsafe {
+            self.handler.HandleTextEditTextChangedEvent(sender, change_type.into(), texts.get_array())?
+        };
+        Ok(())
+    }
+}
+
+impl From<IUIAutomationTextEditTextChangedEventHandler> for UITextEditTextChangedEventHandler {
+    fn from(handler: IUIAutomationTextEditTextChangedEventHandler) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+impl From<&IUIAutomationTextEditTextChangedEventHandler> for UITextEditTextChangedEventHandler {
+    fn from(value: &IUIAutomationTextEditTextChangedEventHandler) -> Self {
+        value.clone().into()
+    }
+}
+
+impl From<UITextEditTextChangedEventHandler> for IUIAutomationTextEditTextChangedEventHandler {
+    fn from(value: UITextEditTextChangedEventHandler) -> Self {
+        value.handler
+    }
+}
+
+impl AsRef<IUIAutomationTextEditTextChangedEventHandler> for UITextEditTextChangedEventHandler {
+    fn as_ref(&self) -> &IUIAutomationTextEditTextChangedEventHandler {
+        &self.handler
+    }
+}
+
+impl Param<IUIAutomationTextEditTextChangedEventHandler> for UITextEditTextChangedEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationTextEditTextChangedEventHandler> { unsafe {
+        self.handler.param()
+    }}
+}
+
+impl Param<IUIAutomationTextEditTextChangedEventHandler> for &UITextEditTextChangedEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationTextEditTextChangedEventHandler> { unsafe {
+        (&self.handler).param()
+    }}
+}
+
+/// A wrapper for windows `IUIAutomationActiveTextPositionChangedEventHandler` interface. 
+/// 
+/// Exposes a method to handle events that are raised when the active text position changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UIActiveTextPositionChangedEventHandler {
+    handler: IUIAutomationActiveTextPositionChangedEventHandler
+}
+
+impl UIActiveTextPositionChangedEventHandler {
+    /// Handles an event that is raised when the active text position changes, such as a navigation to a heading in a document.
+    pub fn handle_active_text_position_changed_event(&self, sender: &UIElement, range: Option<&UITextRange>) -> Result<()> {
+        let range: Option<&IUIAutomationTextRange> = range.map(|r| r.as_ref());
+        unsafe {
+            self.handler.HandleActiveTextPositionChangedEvent(sender, range)?
+        };
+        Ok(())
+    }
+}
+
+impl From<IUIAutomationActiveTextPositionChangedEventHandler> for UIActiveTextPositionChangedEventHandler {
+    fn from(handler: IUIAutomationActiveTextPositionChangedEventHandler) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+impl From<&IUIAutomationActiveTextPositionChangedEventHandler> for UIActiveTextPositionChangedEventHandler {
+    fn from(value: &IUIAutomationActiveTextPositionChangedEventHandler) -> Self {
+        value.clone().into()
+    }
+}
+
+impl From<UIActiveTextPositionChangedEventHandler> for IUIAutomationActiveTextPositionChangedEventHandler {
+    fn from(value: UIActiveTextPositionChangedEventHandler) -> Self {
+        value.handler
+    }
+}
+
+impl AsRef<IUIAutomationActiveTextPositionChangedEventHandler> for UIActiveTextPositionChangedEventHandler {
+    fn as_ref(&self) -> &IUIAutomationActiveTextPositionChangedEventHandler {
+        &self.handler
+    }
+}
+
+impl Param<IUIAutomationActiveTextPositionChangedEventHandler> for UIActiveTextPositionChangedEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationActiveTextPositionChangedEventHandler> { unsafe {
+        self.handler.param()
+    }}
+}
+
+impl Param<IUIAutomationActiveTextPositionChangedEventHandler> for &UIActiveTextPositionChangedEventHandler {
+    unsafe fn param(self) -> windows::core::ParamValue<IUIAutomationActiveTextPositionChangedEventHandler> { unsafe {
+        (&self.handler).param()
+    }}
+}
+
 /// Defines a custom handler for `IUIAutomationEventHandler`.
 pub trait CustomEventHandler {
     fn handle(&self, sender: &UIElement, event_type: UIEventType) -> Result<()>;
@@ -449,6 +923,102 @@ impl From<Box<CustomFocusChangedEventHandlerFn>> for UIFocusChangedEventHandler
     }
 }
 
+/// Defines a custom handler for `IUIAutomationNotificationEventHandler`.
+pub trait CustomNotificationEventHandler {
+    fn handle(&self, sender: &UIElement, kind: NotificationKind, processing: NotificationProcessing, display: &str, activity_id: &str) -> Result<()>;
+}
+
+impl <T> From<T> for UINotificationEventHandler where T: CustomNotificationEventHandler + 'static {
+    fn from(value: T) -> Self {
+        let handler = handlers::AutomationNotificationEventHandler::from(value);
+        let handler: IUIAutomationNotificationEventHandler = handler.into();
+        handler.into()
+    }
+}
+
+/// Defines a custom handler function for `IUIAutomationNotificationEventHandler`.
+pub type CustomNotificationEventHandlerFn = dyn Fn(&UIElement, NotificationKind, NotificationProcessing, &str, &str) -> Result<()>;
+
+impl From<Box<CustomNotificationEventHandlerFn>> for UINotificationEventHandler {
+    fn from(value: Box<CustomNotificationEventHandlerFn>) -> Self {
+        let handler = functions::AutomationNotificationEventHandler::from(value);
+        let handler: IUIAutomationNotificationEventHandler = handler.into();
+        handler.into()
+    }
+}
+
+/// Defines a custom handler for `IUIAutomationChangesEventHandler`.
+pub trait CustomChangesEventHandler {
+    fn handle(&self, sender: &UIElement, changes: &[UIChangeInfo]) -> Result<()>;
+}
+
+impl <T> From<T> for UIChangesEventHandler where T: CustomChangesEventHandler + 'static {
+    fn from(value: T) -> Self {
+        let handler = handlers::AutomationChangesEventHandler::from(value);
+        let handler: IUIAutomationChangesEventHandler = handler.into();
+        handler.into()
+    }
+}
+
+/// Defines a custom handler function for `IUIAutomationChangesEventHandler`.
+pub type CustomChangesEventHandlerFn = dyn Fn(&UIElement, &[UIChangeInfo]) -> Result<()>;
+
+impl From<Box<CustomChangesEventHandlerFn>> for UIChangesEventHandler {
+    fn from(value: Box<CustomChangesEventHandlerFn>) -> Self {
+        let handler = functions::AutomationChangesEventHandler::from(value);
+        let handler: IUIAutomationChangesEventHandler = handler.into();
+        handler.into()
+    }
+}
+
+/// Defines a custom handler for `IUIAutomationTextEditTextChangedEventHandler`.
+pub trait CustomTextEditTextChangedEventHandler {
+    fn handle(&self, sender: &UIElement, change_type: TextEditChangeType, texts: &[String]) -> Result<()>;
+}
+
+impl <T> From<T> for UITextEditTextChangedEventHandler where T: CustomTextEditTextChangedEventHandler + 'static {
+    fn from(value: T) -> Self {
+        let handler = handlers::AutomationTextEditTextChangedEventHandler::from(value);
+        let handler: IUIAutomationTextEditTextChangedEventHandler = handler.into();
+        handler.into()
+    }
+}
+
+/// Defines a custom handler function for `IUIAutomationTextEditTextChangedEventHandler`.
+pub type CustomTextEditTextChangedEventHandlerFn = dyn Fn(&UIElement, TextEditChangeType, &[String]) -> Result<()>;
+
+impl From<Box<CustomTextEditTextChangedEventHandlerFn>> for UITextEditTextChangedEventHandler {
+    fn from(value: Box<CustomTextEditTextChangedEventHandlerFn>) -> Self {
+        let handler = functions::AutomationTextEditTextChangedEventHandler::from(value);
+        let handler: IUIAutomationTextEditTextChangedEventHandler = handler.into();
+        handler.into()
+    }
+}
+
+/// Defines a custom handler for `IUIAutomationActiveTextPositionChangedEventHandler`.
+pub trait CustomActiveTextPositionChangedEventHandler {
+    fn handle(&self, sender: &UIElement, range: Option<UITextRange>) -> Result<()>;
+}
+
+impl <T> From<T> for UIActiveTextPositionChangedEventHandler where T: CustomActiveTextPositionChangedEventHandler + 'static {
+    fn from(value: T) -> Self {
+        let handler = handlers::AutomationActiveTextPositionChangedEventHandler::from(value);
+        let handler: IUIAutomationActiveTextPositionChangedEventHandler = handler.into();
+        handler.into()
+    }
+}
+
+/// Defines a custom handler function for `IUIAutomationActiveTextPositionChangedEventHandler`.
+pub type CustomActiveTextPositionChangedEventHandlerFn = dyn Fn(&UIElement, Option<UITextRange>) -> Result<()>;
+
+impl From<Box<CustomActiveTextPositionChangedEventHandlerFn>> for UIActiveTextPositionChangedEventHandler {
+    fn from(value: Box<CustomActiveTextPositionChangedEventHandlerFn>) -> Self {
+        let handler = functions::AutomationActiveTextPositionChangedEventHandler::from(value);
+        let handler: IUIAutomationActiveTextPositionChangedEventHandler = handler.into();
+        handler.into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use windows::Win32::UI::Accessibility::UIA_DropTarget_DroppedEventId;
@@ -484,7 +1054,7 @@ mod tests {
         let root = automation.get_root_element().unwrap();
 
         let handler = UIEventHandler::from(MyEventHandler {});
-        automation.add_automation_event_handler(UIEventType::TextEdit_TextChanged, &root, TreeScope::Subtree, None, &handler).unwrap();
+        automation.add_automation_event_handler(UIEventType::TextEdit_TextChanged, &root, TreeScope::Subtree, None, &handler).unwrap().detach();
         automation.remove_automation_event_handler(UIEventType::TextEdit_TextChanged, &root, &handler).unwrap();
 
         let handle_fn: Box<CustomEventHandlerFn> = Box::new(|sender, event_type| {
@@ -493,7 +1063,148 @@ mod tests {
         });
 
         let handler = UIEventHandler::from(handle_fn);
-        automation.add_automation_event_handler(UIEventType::Text_TextChanged, &root, TreeScope::Subtree, None, &handler).unwrap();
-        automation.remove_automation_event_handler(UIEventType::Text_TextChanged, &root, &handler).unwrap();
+        let registration = automation.add_automation_event_handler(UIEventType::Text_TextChanged, &root, TreeScope::Subtree, None, &handler).unwrap();
+        std::thread::spawn(move || drop(registration)).join().unwrap();
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+
+        let subscription = automation.subscribe(UIEventType::Window_WindowOpened, &root, TreeScope::Subtree).unwrap();
+        let focus = automation.subscribe_focus_changed().unwrap();
+
+        let handle = std::thread::spawn(move || {
+            while let Ok(event) = subscription.recv_timeout(std::time::Duration::from_millis(200)) {
+                println!("{:?}", event);
+            }
+        });
+        while let Ok(event) = focus.try_recv() {
+            println!("{:?}", event);
+        }
+        focus.unsubscribe().unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_add_event_handler() {
+        use crate::types::UIProperty;
+
+        use super::EventSpec;
+
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+
+        let specs = [
+            EventSpec::automation(UIEventType::Window_WindowOpened, &root, TreeScope::Subtree),
+            EventSpec::property_changed(&root, TreeScope::Subtree, &[UIProperty::Name]),
+            EventSpec::structure_changed(&root, TreeScope::Children),
+            EventSpec::focus_changed()
+        ];
+        for spec in specs.iter() {
+            let registration = automation.add_event_handler(spec, |event| {
+                println!("{} {:?}", event.get_timestamp(), event);
+                Ok(())
+            }).unwrap();
+            registration.remove().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_wait_for_event() {
+        use crate::errors::ERR_TIMEOUT;
+        use crate::types::UIProperty;
+
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+
+        let result = automation.wait_for_event_after(UIEventType::Window_WindowOpened, &root, TreeScope::Children, |window| {
+            window.get_name().map(|name| name == "You can find nothing!").unwrap_or(false)
+        }, || Ok(()), 300);
+        assert_eq!(result.unwrap_err().code(), ERR_TIMEOUT);
+
+        let result = automation.wait_for_property_changed(&root, TreeScope::Element, UIProperty::Name, |_, _| true, 100);
+        assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_notification_event_handler() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        use windows::Win32::UI::Accessibility::UIA_SummaryChangeId;
+
+        use crate::types::NotificationKind;
+        use crate::types::NotificationProcessing;
+        use crate::types::TextEditChangeType;
+
+        use super::CustomNotificationEventHandlerFn;
+        use super::EventSpec;
+        use super::UINotificationEventHandler;
+
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+
+        let captured = Rc::new(RefCell::new(None));
+        let captured_by_handler = captured.clone();
+        let handle_fn: Box<CustomNotificationEventHandlerFn> = Box::new(move |_, kind, processing, display, activity_id| {
+            *captured_by_handler.borrow_mut() = Some((kind, processing, display.to_string(), activity_id.to_string()));
+            Ok(())
+        });
+        let handler = UINotificationEventHandler::from(handle_fn);
+        handler.handle_notification_event(&root, NotificationKind::ActionCompleted, NotificationProcessing::MostRecent, "text", "id").unwrap();
+        assert_eq!(captured.borrow().clone(), Some((NotificationKind::ActionCompleted, NotificationProcessing::MostRecent, "text".to_string(), "id".to_string())));
+        automation.add_notification_event_handler(&root, TreeScope::Subtree, None, &handler).unwrap().remove().unwrap();
+
+        let specs = [
+            EventSpec::changes(&root, TreeScope::Subtree, &[UIA_SummaryChangeId.0]),
+            EventSpec::text_edit_text_changed(&root, TreeScope::Subtree, TextEditChangeType::AutoCorrect),
+            EventSpec::active_text_position_changed(&root, TreeScope::Subtree)
+        ];
+        for spec in specs.iter() {
+            let registration = automation.add_event_handler(spec, |event| {
+                println!("{:?}", event);
+                Ok(())
+            }).unwrap();
+            registration.remove().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_changes_event_handler() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        use windows::Win32::UI::Accessibility::UIA_SummaryChangeId;
+
+        use crate::variants::Value;
+
+        use super::CustomChangesEventHandlerFn;
+        use super::UIChangeInfo;
+        use super::UIChangesEventHandler;
+
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+
+        let captured = Rc::new(RefCell::new(Vec::new()));
+        let captured_by_handler = captured.clone();
+        let handle_fn: Box<CustomChangesEventHandlerFn> = Box::new(move |_, changes| {
+            captured_by_handler.borrow_mut().extend_from_slice(changes);
+            Ok(())
+        });
+        let handler = UIChangesEventHandler::from(handle_fn);
+        let change = UIChangeInfo {
+            uia_id: UIA_SummaryChangeId.0,
+            payload: Value::STRING("summary".to_string()),
+            extra_info: Value::I4(42)
+        };
+        handler.handle_changes_event(&root, &[change]).unwrap();
+
+        let captured = captured.borrow();
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].uia_id, UIA_SummaryChangeId.0);
+        assert!(matches!(&captured[0].payload, Value::STRING(payload) if payload == "summary"));
+        assert!(matches!(captured[0].extra_info, Value::I4(42)));
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::ops::Deref;
+
+use windows::Win32::System::Com::APTTYPE;
+use windows::Win32::System::Com::APTTYPE_MTA;
+use windows::Win32::System::Com::APTTYPEQUALIFIER;
+use windows::Win32::System::Com::CoGetApartmentType;
+
+use crate::errors::ERR_INVALID_OBJECT;
+use crate::Error;
+use crate::Result;
+
+/// A UI Automation object of the multithreaded apartment, which can be sent to other threads.
+///
+/// The COM interfaces of windows-rs are not `Send`, but the UI Automation client objects can be used on any thread of the apartment they belong to.
+/// The objects passed in by the UI Automation event callbacks belong to the multithreaded apartment, so the events hold them by this wrapper.
+/// It is only created by this crate on the threads of the multithreaded apartment, so the objects of a single-threaded apartment never leave their thread.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MtaObject<T>(T);
+
+// SAFETY: `new()` only wraps the COM objects used on a thread of the multithreaded apartment, which are valid on all the threads of it.
+// A thread without COM initialized joins the multithreaded apartment implicitly when it uses them.
+unsafe impl<T> Send for MtaObject<T> {}
+
+impl<T> MtaObject<T> {
+    /// Wraps `object`, whose only values which are not `Send` must be UI Automation objects.
+    ///
+    /// Returns an error if the current thread is not in the multithreaded apartment.
+    pub(crate) fn new(object: T) -> Result<Self> {
+        if is_mta() {
+            Ok(Self(object))
+        } else {
+            Err(Error::new(ERR_INVALID_OBJECT, "the object does not belong to the multithreaded apartment"))
+        }
+    }
+
+    /// Retrieves the inner object.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for MtaObject<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for MtaObject<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Debug> Debug for MtaObject<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Display> Display for MtaObject<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Checks whether the current thread is in the multithreaded apartment, including joining it implicitly.
+fn is_mta() -> bool {
+    let mut apt_type = APTTYPE::default();
+    let mut qualifier = APTTYPEQUALIFIER::default();
+    unsafe { CoGetApartmentType(&mut apt_type, &mut qualifier) }.is_ok() && apt_type == APTTYPE_MTA
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use windows::Win32::System::Com::COINIT_APARTMENTTHREADED;
+    use windows::Win32::System::Com::CoInitializeEx;
+    use windows::Win32::System::Com::CoUninitialize;
+
+    use crate::UIAutomation;
+
+    use super::MtaObject;
+
+    #[test]
+    fn test_mta_object() {
+        let automation = UIAutomation::new().unwrap();
+        let root = MtaObject::new(automation.get_root_element().unwrap()).unwrap();
+        let name = thread::spawn(move || root.get_name().unwrap()).join().unwrap();
+        println!("root: {}", name);
+
+        thread::spawn(|| {
+            unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok().unwrap() };
+            assert!(MtaObject::new(0).is_err());
+            unsafe { CoUninitialize() };
+        }).join().unwrap();
+    }
+}
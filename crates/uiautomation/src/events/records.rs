@@ -0,0 +1,718 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread::sleep;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Local;
+use windows::core::HRESULT;
+
+use crate::core::ElementRef;
+use crate::errors::ERR_FORMAT;
+use crate::errors::ERR_INVALID_OBJECT;
+use crate::jsons::Json;
+use crate::types::NotificationKind;
+use crate::types::NotificationProcessing;
+use crate::types::StructureChangeType;
+use crate::types::TextEditChangeType;
+use crate::types::TreeScope;
+use crate::types::UIProperty;
+use crate::variants::Value;
+use crate::variants::Variant;
+use crate::Error;
+use crate::Result;
+use crate::UIAutomation;
+use crate::UIElement;
+
+use super::CustomEventHandler;
+use super::CustomPropertyChangedEventHandler;
+use super::EventRegistration;
+use super::EventSpec;
+use super::MtaObject;
+use super::UIEvent;
+use super::UIEventType;
+
+/// A change of a recorded changes event.
+#[derive(Clone)]
+pub struct RecordedChange {
+    pub uia_id: i32,
+    pub payload: Value,
+    pub extra_info: Value
+}
+
+/// The kind-specific payload of a recorded event.
+#[derive(Clone)]
+pub enum RecordedPayload {
+    Automation {
+        event_type: UIEventType
+    },
+    PropertyChanged {
+        property: UIProperty,
+        value: Value
+    },
+    StructureChanged {
+        change_type: StructureChangeType,
+        runtime_id: Option<Vec<i32>>
+    },
+    FocusChanged,
+    Notification {
+        kind: NotificationKind,
+        processing: NotificationProcessing,
+        display: String,
+        activity_id: String
+    },
+    Changes {
+        changes: Vec<RecordedChange>
+    },
+    TextEditTextChanged {
+        change_type: TextEditChangeType,
+        texts: Vec<String>
+    },
+    ActiveTextPositionChanged
+}
+
+/// An event recorded by `EventRecorder`, which holds no UI Automation objects.
+///
+/// The sender is kept as an `ElementRef` snapshot. A recorded event is written as one line of JSON.
+#[derive(Clone)]
+pub struct RecordedEvent {
+    pub timestamp: DateTime<Local>,
+    pub sender: ElementRef,
+    pub payload: RecordedPayload
+}
+
+impl RecordedEvent {
+    /// Takes a snapshot of `event` and its sender, locating the sender by `automation`.
+    pub fn from_event(automation: &UIAutomation, event: &UIEvent) -> Result<Self> {
+        let payload = match event {
+            UIEvent::Automation { event_type, .. } => RecordedPayload::Automation { event_type: *event_type },
+            UIEvent::PropertyChanged { property, value, .. } => RecordedPayload::PropertyChanged { property: *property, value: value.as_ref().clone() },
+            UIEvent::StructureChanged { change_type, runtime_id, .. } => RecordedPayload::StructureChanged { change_type: *change_type, runtime_id: runtime_id.clone() },
+            UIEvent::FocusChanged { .. } => RecordedPayload::FocusChanged,
+            UIEvent::Notification { kind, processing, display, activity_id, .. } => RecordedPayload::Notification {
+                kind: *kind,
+                processing: *processing,
+                display: display.clone(),
+                activity_id: activity_id.clone()
+            },
+            UIEvent::Changes { changes, .. } => RecordedPayload::Changes {
+                changes: changes.iter().map(|c| RecordedChange {
+                    uia_id: c.uia_id,
+                    payload: c.payload.clone(),
+                    extra_info: c.extra_info.clone()
+                }).collect()
+            },
+            UIEvent::TextEditTextChanged { change_type, texts, .. } => RecordedPayload::TextEditTextChanged { change_type: *change_type, texts: texts.clone() },
+            UIEvent::ActiveTextPositionChanged { .. } => RecordedPayload::ActiveTextPositionChanged
+        };
+
+        Ok(Self {
+            timestamp: event.get_timestamp(),
+            sender: event.get_sender().to_ref(automation)?,
+            payload
+        })
+    }
+
+    fn to_json(&self) -> Json {
+        let sender = &self.sender;
+        let sender = Json::object([
+            ("runtime_id", Json::Array(sender.runtime_id.iter().map(Json::number).collect())),
+            ("process_id", Json::number(sender.process_id)),
+            ("window_handle", Json::number(sender.window_handle)),
+            ("control_type", sender.control_type.map(|t| Json::number(t as i32)).unwrap_or(Json::Null)),
+            ("automation_id", Json::string(&sender.automation_id)),
+            ("classname", Json::string(&sender.classname)),
+            ("name", Json::string(&sender.name))
+        ]);
+
+        let (kind, payload) = match &self.payload {
+            RecordedPayload::Automation { event_type } => ("automation", Json::object([
+                ("event_type", Json::number(*event_type as i32))
+            ])),
+            RecordedPayload::PropertyChanged { property, value } => ("property_changed", Json::object([
+                ("property", Json::number(*property as i32)),
+                ("value", value_to_json(value))
+            ])),
+            RecordedPayload::StructureChanged { change_type, runtime_id } => ("structure_changed", Json::object([
+                ("change_type", Json::number(*change_type as i32)),
+                ("runtime_id", runtime_id.as_ref().map(|id| Json::Array(id.iter().map(Json::number).collect())).unwrap_or(Json::Null))
+            ])),
+            RecordedPayload::FocusChanged => ("focus_changed", Json::Object(Vec::new())),
+            RecordedPayload::Notification { kind, processing, display, activity_id } => ("notification", Json::object([
+                ("kind", Json::number(*kind as i32)),
+                ("processing", Json::number(*processing as i32)),
+                ("display", Json::string(display)),
+                ("activity_id", Json::string(activity_id))
+            ])),
+            RecordedPayload::Changes { changes } => ("changes", Json::object([
+                ("changes", Json::Array(changes.iter().map(|c| Json::object([
+                    ("uia_id", Json::number(c.uia_id)),
+                    ("payload", value_to_json(&c.payload)),
+                    ("extra_info", value_to_json(&c.extra_info))
+                ])).collect()))
+            ])),
+            RecordedPayload::TextEditTextChanged { change_type, texts } => ("text_edit_text_changed", Json::object([
+                ("change_type", Json::number(*change_type as i32)),
+                ("texts", Json::Array(texts.iter().map(Json::string).collect()))
+            ])),
+            RecordedPayload::ActiveTextPositionChanged => ("active_text_position_changed", Json::Object(Vec::new()))
+        };
+
+        Json::object([
+            ("kind", Json::string(kind)),
+            ("timestamp", Json::string(self.timestamp.to_rfc3339())),
+            ("sender", sender),
+            ("payload", payload)
+        ])
+    }
+
+    fn from_json(json: &Json) -> Result<Self> {
+        let timestamp = DateTime::parse_from_rfc3339(json.field("timestamp")?.as_str()?)
+            .map_err(|e| Error::new(ERR_FORMAT, &e.to_string()))?
+            .with_timezone(&Local);
+
+        let sender = json.field("sender")?;
+        let control_type = sender.field("control_type")?;
+        let sender = ElementRef {
+            runtime_id: sender.field("runtime_id")?.as_array()?.iter().map(|id| id.as_number()).collect::<Result<Vec<i32>>>()?,
+            process_id: sender.field("process_id")?.as_number()?,
+            window_handle: sender.field("window_handle")?.as_number()?,
+            control_type: if control_type.is_null() { None } else { Some(control_type.as_number::<i32>()?.try_into()?) },
+            automation_id: sender.field("automation_id")?.as_str()?.to_string(),
+            classname: sender.field("classname")?.as_str()?.to_string(),
+            name: sender.field("name")?.as_str()?.to_string()
+        };
+
+        let payload = json.field("payload")?;
+        let payload = match json.field("kind")?.as_str()? {
+            "automation" => RecordedPayload::Automation {
+                event_type: payload.field("event_type")?.as_number::<i32>()?.try_into()?
+            },
+            "property_changed" => RecordedPayload::PropertyChanged {
+                property: payload.field("property")?.as_number::<i32>()?.try_into()?,
+                value: value_from_json(payload.field("value")?)?
+            },
+            "structure_changed" => {
+                let runtime_id = payload.field("runtime_id")?;
+                RecordedPayload::StructureChanged {
+                    change_type: payload.field("change_type")?.as_number::<i32>()?.try_into()?,
+                    runtime_id: if runtime_id.is_null() { None } else { Some(runtime_id.as_array()?.iter().map(|id| id.as_number()).collect::<Result<Vec<i32>>>()?) }
+                }
+            },
+            "focus_changed" => RecordedPayload::FocusChanged,
+            "notification" => RecordedPayload::Notification {
+                kind: payload.field("kind")?.as_number::<i32>()?.try_into()?,
+                processing: payload.field("processing")?.as_number::<i32>()?.try_into()?,
+                display: payload.field("display")?.as_str()?.to_string(),
+                activity_id: payload.field("activity_id")?.as_str()?.to_string()
+            },
+            "changes" => RecordedPayload::Changes {
+                changes: payload.field("changes")?.as_array()?.iter().map(|c| Ok(RecordedChange {
+                    uia_id: c.field("uia_id")?.as_number()?,
+                    payload: value_from_json(c.field("payload")?)?,
+                    extra_info: value_from_json(c.field("extra_info")?)?
+                })).collect::<Result<Vec<_>>>()?
+            },
+            "text_edit_text_changed" => RecordedPayload::TextEditTextChanged {
+                change_type: payload.field("change_type")?.as_number::<i32>()?.try_into()?,
+                texts: payload.field("texts")?.as_array()?.iter().map(|t| t.as_str().map(|t| t.to_string())).collect::<Result<Vec<_>>>()?
+            },
+            "active_text_position_changed" => RecordedPayload::ActiveTextPositionChanged,
+            kind => return Err(Error::new(ERR_FORMAT, &format!("unknown event kind: {}", kind)))
+        };
+
+        Ok(Self {
+            timestamp,
+            sender,
+            payload
+        })
+    }
+}
+
+impl Display for RecordedEvent {
+    /// Writes the event as one line of JSON.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+impl Debug for RecordedEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RecordedEvent({})", self)
+    }
+}
+
+impl FromStr for RecordedEvent {
+    type Err = Error;
+
+    /// Parses an event from one line of JSON.
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_json(&Json::parse(s)?)
+    }
+}
+
+macro_rules! numbers_to_json {
+    ($type: expr, $values: expr) => {
+        Json::object([("type", Json::string($type)), ("value", Json::Array($values.iter().map(Json::number).collect()))])
+    };
+}
+
+/// Writes `value` as `{"type": .., "value": ..}`. The values which can not be recorded, such as COM objects, are written as strings.
+fn value_to_json(value: &Value) -> Json {
+    let (value_type, json) = match value {
+        Value::EMPTY => ("EMPTY", Json::Null),
+        Value::NULL => ("NULL", Json::Null),
+        Value::VOID => ("VOID", Json::Null),
+        Value::I1(v) => ("I1", Json::number(v)),
+        Value::I2(v) => ("I2", Json::number(v)),
+        Value::I4(v) => ("I4", Json::number(v)),
+        Value::I8(v) => ("I8", Json::number(v)),
+        Value::INT(v) => ("INT", Json::number(v)),
+        Value::UI1(v) => ("UI1", Json::number(v)),
+        Value::UI2(v) => ("UI2", Json::number(v)),
+        Value::UI4(v) => ("UI4", Json::number(v)),
+        Value::UI8(v) => ("UI8", Json::number(v)),
+        Value::UINT(v) => ("UINT", Json::number(v)),
+        Value::R4(v) => ("R4", Json::number(v)),
+        Value::R8(v) => ("R8", Json::number(v)),
+        Value::CURRENCY(v) => ("CURRENCY", Json::number(v)),
+        Value::DATE(v) => ("DATE", Json::number(v)),
+        Value::STRING(v) => ("STRING", Json::string(v)),
+        Value::ERROR(v) => ("ERROR", Json::number(v.0)),
+        Value::HRESULT(v) => ("HRESULT", Json::number(v.0)),
+        Value::BOOL(v) => ("BOOL", Json::Bool(*v)),
+        Value::ArrayBool(v) => ("ArrayBool", Json::Array(v.iter().map(|b| Json::Bool(*b)).collect())),
+        Value::ArrayR8(v) => return numbers_to_json!("ArrayR8", v),
+        Value::ArrayI2(v) => return numbers_to_json!("ArrayI2", v),
+        Value::ArrayI4(v) => return numbers_to_json!("ArrayI4", v),
+        Value::ArrayI8(v) => return numbers_to_json!("ArrayI8", v),
+        Value::ArrayUI2(v) => return numbers_to_json!("ArrayUI2", v),
+        Value::ArrayUI4(v) => return numbers_to_json!("ArrayUI4", v),
+        Value::ArrayUI8(v) => return numbers_to_json!("ArrayUI8", v),
+        Value::ArrayString(v) => ("ArrayString", Json::Array(v.iter().map(Json::string).collect())),
+        other => ("STRING", Json::string(other.to_string()))
+    };
+    Json::object([("type", Json::string(value_type)), ("value", json)])
+}
+
+fn value_from_json(json: &Json) -> Result<Value> {
+    fn numbers<T: FromStr>(value: &Json) -> Result<Vec<T>> {
+        value.as_array()?.iter().map(|v| v.as_number()).collect()
+    }
+
+    let value = json.field("value")?;
+    let value = match json.field("type")?.as_str()? {
+        "EMPTY" => Value::EMPTY,
+        "NULL" => Value::NULL,
+        "VOID" => Value::VOID,
+        "I1" => Value::I1(value.as_number()?),
+        "I2" => Value::I2(value.as_number()?),
+        "I4" => Value::I4(value.as_number()?),
+        "I8" => Value::I8(value.as_number()?),
+        "INT" => Value::INT(value.as_number()?),
+        "UI1" => Value::UI1(value.as_number()?),
+        "UI2" => Value::UI2(value.as_number()?),
+        "UI4" => Value::UI4(value.as_number()?),
+        "UI8" => Value::UI8(value.as_number()?),
+        "UINT" => Value::UINT(value.as_number()?),
+        "R4" => Value::R4(value.as_number()?),
+        "R8" => Value::R8(value.as_number()?),
+        "CURRENCY" => Value::CURRENCY(value.as_number()?),
+        "DATE" => Value::DATE(value.as_number()?),
+        "STRING" => Value::STRING(value.as_str()?.to_string()),
+        "ERROR" => Value::ERROR(HRESULT(value.as_number()?)),
+        "HRESULT" => Value::HRESULT(HRESULT(value.as_number()?)),
+        "BOOL" => Value::BOOL(value.as_bool()?),
+        "ArrayBool" => Value::ArrayBool(value.as_array()?.iter().map(|v| v.as_bool()).collect::<Result<Vec<_>>>()?),
+        "ArrayR8" => Value::ArrayR8(numbers(value)?),
+        "ArrayI2" => Value::ArrayI2(numbers(value)?),
+        "ArrayI4" => Value::ArrayI4(numbers(value)?),
+        "ArrayI8" => Value::ArrayI8(numbers(value)?),
+        "ArrayUI2" => Value::ArrayUI2(numbers(value)?),
+        "ArrayUI4" => Value::ArrayUI4(numbers(value)?),
+        "ArrayUI8" => Value::ArrayUI8(numbers(value)?),
+        "ArrayString" => Value::ArrayString(value.as_array()?.iter().map(|v| v.as_str().map(|s| s.to_string())).collect::<Result<Vec<_>>>()?),
+        value_type => return Err(Error::new(ERR_FORMAT, &format!("unknown value type: {}", value_type)))
+    };
+    Ok(value)
+}
+
+/// Records UI Automation events to a JSON Lines file, one `RecordedEvent` per line.
+///
+/// The events are written by the UI Automation event threads. The event handlers are removed and the file is flushed when the recorder is stopped or dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::UIAutomation;
+/// use uiautomation::events::EventRecorder;
+/// use uiautomation::types::TreeScope;
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let notepad = automation.create_matcher().classname("Notepad").find_first().unwrap();
+///
+/// let mut recorder = EventRecorder::create("notepad.jsonl").unwrap();
+/// recorder.record_scope(&automation, &notepad, TreeScope::Subtree).unwrap();
+/// std::thread::sleep(std::time::Duration::from_secs(10));
+/// recorder.stop().unwrap();
+/// ```
+pub struct EventRecorder {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    count: Arc<AtomicUsize>,
+    registrations: Vec<EventRegistration>
+}
+
+impl EventRecorder {
+    /// The event types recorded by `record_scope()`.
+    pub const SCOPE_EVENTS: [UIEventType; 10] = [
+        UIEventType::Window_WindowOpened,
+        UIEventType::Window_WindowClosed,
+        UIEventType::Invoke_Invoked,
+        UIEventType::SelectionItem_ElementSelected,
+        UIEventType::Selection_Invalidated,
+        UIEventType::MenuOpened,
+        UIEventType::MenuClosed,
+        UIEventType::ToolTipOpened,
+        UIEventType::Text_TextChanged,
+        UIEventType::LayoutInvalidated
+    ];
+
+    /// The properties recorded by `record_scope()`.
+    pub const SCOPE_PROPERTIES: [UIProperty; 6] = [
+        UIProperty::Name,
+        UIProperty::IsEnabled,
+        UIProperty::ValueValue,
+        UIProperty::ToggleToggleState,
+        UIProperty::ExpandCollapseExpandCollapseState,
+        UIProperty::SelectionItemIsSelected
+    ];
+
+    /// Creates a recorder which writes the events to `writer`.
+    pub fn new<W>(writer: W) -> Self where W: Write + Send + 'static {
+        Self {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+            count: Arc::new(AtomicUsize::new(0)),
+            registrations: Vec::new()
+        }
+    }
+
+    /// Creates a recorder which writes the events to the file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::create(path).map_err(|e| Error::from(e.to_string()))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Records the events described by `spec`.
+    ///
+    /// It must be called in the multithreaded apartment, such as the threads calling `UIAutomation::new()`, since `automation` locates the senders on the event threads.
+    pub fn record(&mut self, automation: &UIAutomation, spec: &EventSpec) -> Result<()> {
+        let writer = self.writer.clone();
+        let count = self.count.clone();
+        let locator = MtaObject::new(automation.clone())?;
+        let registration = automation.add_event_handler(spec, move |event| {
+            let line = RecordedEvent::from_event(&locator, event)?.to_string();
+            let mut writer = writer.lock().map_err(|_| Error::new(ERR_INVALID_OBJECT, "the recorder is poisoned"))?;
+            writeln!(writer, "{}", line).map_err(|e| Error::from(e.to_string()))?;
+            count.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        })?;
+        self.registrations.push(registration);
+        Ok(())
+    }
+
+    /// Records the common events of `element` and its `scope`: `SCOPE_EVENTS`, the changes of `SCOPE_PROPERTIES` and the structure changes.
+    pub fn record_scope(&mut self, automation: &UIAutomation, element: &UIElement, scope: TreeScope) -> Result<()> {
+        for event_type in Self::SCOPE_EVENTS {
+            self.record(automation, &EventSpec::automation(event_type, element, scope))?;
+        }
+        self.record(automation, &EventSpec::property_changed(element, scope, &Self::SCOPE_PROPERTIES))?;
+        self.record(automation, &EventSpec::structure_changed(element, scope))
+    }
+
+    /// Retrieves the count of the recorded events.
+    pub fn get_count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Removes the event handlers and flushes the recorded events.
+    pub fn stop(mut self) -> Result<()> {
+        for registration in self.registrations.drain(..) {
+            registration.remove()?;
+        }
+        self.flush()
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut writer = self.writer.lock().map_err(|_| Error::new(ERR_INVALID_OBJECT, "the recorder is poisoned"))?;
+        writer.flush().map_err(|e| Error::from(e.to_string()))
+    }
+}
+
+impl Drop for EventRecorder {
+    fn drop(&mut self) {
+        self.registrations.clear();
+        let _ = self.flush();
+    }
+}
+
+/// The result of `EventReplayer::replay_events()` and `EventReplayer::replay_property_changed()`.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaySummary {
+    /// The count of the events passed to the handler, including the ones with the stand-in sender.
+    pub handled: usize,
+    /// The events whose sender could not be resolved.
+    pub unresolved: Vec<RecordedEvent>
+}
+
+/// Replays the events recorded by `EventRecorder`, with the original or accelerated timing.
+///
+/// `replay()` does not call UI Automation at all, so the logic driven by the recorded events can be tested without a live application.
+/// `replay_events()` and `replay_property_changed()` feed the recorded events into the custom handlers, resolving the senders by `UIAutomation::resolve()`
+/// and reporting the events whose sender is gone in a `ReplaySummary`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::events::EventReplayer;
+/// use uiautomation::events::RecordedPayload;
+///
+/// let replayer = EventReplayer::from_file("notepad.jsonl").unwrap().speed(0.0);
+/// let mut opened = 0;
+/// replayer.replay(|event| {
+///     if let RecordedPayload::Automation { .. } = event.payload {
+///         opened += 1;
+///     }
+///     Ok(())
+/// }).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventReplayer {
+    events: Vec<RecordedEvent>,
+    speed: f64
+}
+
+impl EventReplayer {
+    /// Creates a replayer of `events`, with the original timing.
+    pub fn new(events: Vec<RecordedEvent>) -> Self {
+        Self {
+            events,
+            speed: 1.0
+        }
+    }
+
+    /// Parses the events from JSON Lines `text`. The empty lines are skipped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut events = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let event = line.parse::<RecordedEvent>().map_err(|e| Error::new(ERR_FORMAT, &format!("line {}: {}", index + 1, e.message())))?;
+            events.push(event);
+        }
+        Ok(Self::new(events))
+    }
+
+    /// Loads the events from the JSON Lines file at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| Error::from(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    /// Sets the replay speed. `1.0` keeps the original timing, `2.0` replays twice as fast, and `0.0` replays without waiting.
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Retrieves the recorded events.
+    pub fn get_events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Feeds the recorded events into `handler` in order.
+    pub fn replay<F>(&self, mut handler: F) -> Result<()> where F: FnMut(&RecordedEvent) -> Result<()> {
+        let mut prev: Option<DateTime<Local>> = None;
+        for event in self.events.iter() {
+            if let Some(prev) = prev {
+                self.wait(event.timestamp - prev);
+            }
+            prev = Some(event.timestamp);
+
+            handler(event)?;
+        }
+        Ok(())
+    }
+
+    /// Feeds the recorded automation events into `handler`.
+    ///
+    /// The senders are resolved by `UIAutomation::resolve()`. When a sender can not be resolved, `handler` receives `stand_in` instead,
+    /// or is not called if `stand_in` is `None`. Either way the event is reported in `ReplaySummary::unresolved`.
+    pub fn replay_events(&self, automation: &UIAutomation, stand_in: Option<&UIElement>, handler: &dyn CustomEventHandler) -> Result<ReplaySummary> {
+        self.replay_resolved(automation, stand_in, |payload| matches!(payload, RecordedPayload::Automation { .. }), |event, sender| {
+            if let RecordedPayload::Automation { event_type } = event.payload {
+                handler.handle(sender, event_type)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Feeds the recorded property-changed events into `handler`.
+    ///
+    /// The senders are resolved the same as `replay_events()`, falling back to `stand_in`.
+    pub fn replay_property_changed(&self, automation: &UIAutomation, stand_in: Option<&UIElement>, handler: &dyn CustomPropertyChangedEventHandler) -> Result<ReplaySummary> {
+        self.replay_resolved(automation, stand_in, |payload| matches!(payload, RecordedPayload::PropertyChanged { .. }), |event, sender| {
+            if let RecordedPayload::PropertyChanged { property, ref value } = event.payload {
+                handler.handle(sender, property, Variant::from(value.clone()))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Replays the events accepted by `filter`, resolving their senders for `handler`.
+    fn replay_resolved<P, F>(&self, automation: &UIAutomation, stand_in: Option<&UIElement>, filter: P, mut handler: F) -> Result<ReplaySummary>
+        where P: Fn(&RecordedPayload) -> bool, F: FnMut(&RecordedEvent, &UIElement) -> Result<()> {
+        let mut summary = ReplaySummary::default();
+        self.replay(|event| {
+            if !filter(&event.payload) {
+                return Ok(());
+            }
+
+            match automation.resolve(&event.sender) {
+                Ok(sender) => handler(event, &sender)?,
+                Err(_) => {
+                    summary.unresolved.push(event.clone());
+                    match stand_in {
+                        Some(sender) => handler(event, sender)?,
+                        None => return Ok(())
+                    }
+                }
+            }
+            summary.handled += 1;
+            Ok(())
+        })?;
+        Ok(summary)
+    }
+
+    fn wait(&self, elapsed: chrono::TimeDelta) {
+        if self.speed > 0.0 && let Ok(elapsed) = elapsed.to_std() {
+            sleep(Duration::from_secs_f64(elapsed.as_secs_f64() / self.speed));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::events::CustomEventHandler;
+    use crate::events::UIEventType;
+    use crate::types::StructureChangeType;
+    use crate::types::UIProperty;
+    use crate::variants::Value;
+    use crate::Result;
+    use crate::UIAutomation;
+    use crate::UIElement;
+
+    use super::EventReplayer;
+    use super::RecordedEvent;
+    use super::RecordedPayload;
+    use super::value_from_json;
+    use super::value_to_json;
+
+    const RECORDS: &str = r#"
+{"kind":"automation","timestamp":"2026-01-02T10:00:00.000+08:00","sender":{"runtime_id":[42,1],"process_id":100,"window_handle":200,"control_type":50032,"automation_id":"","classname":"Notepad","name":"Save As"},"payload":{"event_type":20016}}
+{"kind":"property_changed","timestamp":"2026-01-02T10:00:00.010+08:00","sender":{"runtime_id":[42,2],"process_id":100,"window_handle":200,"control_type":null,"automation_id":"1001","classname":"Edit","name":"File name:"},"payload":{"property":30045,"value":{"type":"STRING","value":"a \"b\".txt"}}}
+
+{"kind":"structure_changed","timestamp":"2026-01-02T10:00:00.020+08:00","sender":{"runtime_id":[],"process_id":100,"window_handle":0,"control_type":null,"automation_id":"","classname":"","name":""},"payload":{"change_type":0,"runtime_id":[42,3]}}
+"#;
+
+    #[test]
+    fn test_replay_records() {
+        let replayer = EventReplayer::parse(RECORDS).unwrap().speed(0.0);
+        assert_eq!(replayer.get_events().len(), 3);
+
+        let mut kinds = Vec::new();
+        replayer.replay(|event| {
+            match event.payload {
+                RecordedPayload::Automation { event_type } => assert_eq!(event_type, UIEventType::Window_WindowOpened),
+                RecordedPayload::PropertyChanged { property, value: Value::STRING(ref value) } => {
+                    assert_eq!(property, UIProperty::ValueValue);
+                    assert_eq!(value, "a \"b\".txt");
+                },
+                RecordedPayload::StructureChanged { change_type, ref runtime_id } => {
+                    assert_eq!(change_type, StructureChangeType::ChildAdded);
+                    assert_eq!(runtime_id.as_deref(), Some(&[42, 3][..]));
+                },
+                _ => panic!("unexpected event: {:?}", event)
+            }
+            kinds.push(event.sender.name.clone());
+            Ok(())
+        }).unwrap();
+        assert_eq!(kinds, vec!["Save As", "File name:", ""]);
+
+        for event in replayer.get_events() {
+            let line = event.to_string();
+            let parsed: RecordedEvent = line.parse().unwrap();
+            assert_eq!(parsed.to_string(), line);
+        }
+
+        assert!(EventReplayer::parse("{\"kind\":\"unknown\"}").is_err());
+    }
+
+    struct NameHandler(RefCell<Vec<String>>);
+
+    impl CustomEventHandler for NameHandler {
+        fn handle(&self, sender: &UIElement, _event_type: UIEventType) -> Result<()> {
+            self.0.borrow_mut().push(sender.get_name()?);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_replay_unresolved() {
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+        let replayer = EventReplayer::parse(RECORDS).unwrap().speed(0.0);
+
+        let handler = NameHandler(RefCell::new(Vec::new()));
+        let summary = replayer.replay_events(&automation, None, &handler).unwrap();
+        assert_eq!(summary.handled, 0);
+        assert_eq!(summary.unresolved.len(), 1);
+        assert_eq!(summary.unresolved[0].sender.name, "Save As");
+        assert!(handler.0.borrow().is_empty());
+
+        let summary = replayer.replay_events(&automation, Some(&root), &handler).unwrap();
+        assert_eq!(summary.handled, 1);
+        assert_eq!(summary.unresolved.len(), 1);
+        assert_eq!(*handler.0.borrow(), vec![root.get_name().unwrap()]);
+    }
+
+    #[test]
+    fn test_non_finite_values() {
+        let values = [Value::R4(f32::NAN), Value::R8(f64::INFINITY), Value::ArrayR8(vec![1.5, f64::NEG_INFINITY, f64::NAN])];
+        for value in values {
+            let line = value_to_json(&value).to_string();
+            let parsed = value_from_json(&crate::jsons::Json::parse(&line).unwrap()).unwrap();
+            assert_eq!(value_to_json(&parsed).to_string(), line);
+        }
+
+        let line = value_to_json(&Value::ArrayR8(vec![f64::NAN, f64::INFINITY])).to_string();
+        assert_eq!(line, r#"{"type":"ArrayR8","value":["NaN","inf"]}"#);
+        match value_from_json(&crate::jsons::Json::parse(&line).unwrap()).unwrap() {
+            Value::ArrayR8(v) => assert!(v[0].is_nan() && v[1] == f64::INFINITY),
+            other => panic!("unexpected value: {}", other)
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use chrono::Local;
+use windows::Win32::System::Com::COINIT_MULTITHREADED;
+use windows::Win32::System::Com::CoInitializeEx;
+use windows::Win32::System::Com::CoUninitialize;
+use windows::Win32::UI::Accessibility::IUIAutomation;
+use windows::Win32::UI::Accessibility::IUIAutomationActiveTextPositionChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationChangesEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationElement;
+use windows::Win32::UI::Accessibility::IUIAutomationEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationFocusChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationNotificationEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationPropertyChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationStructureChangedEventHandler;
+use windows::Win32::UI::Accessibility::IUIAutomationTextEditTextChangedEventHandler;
+use windows_core::AgileReference;
+use windows_core::Interface;
+
+use crate::patterns::UITextRange;
+use crate::types::NotificationKind;
+use crate::types::NotificationProcessing;
+use crate::types::StructureChangeType;
+use crate::types::TextEditChangeType;
+use crate::types::TreeScope;
+use crate::types::UIProperty;
+use crate::variants::Variant;
+use crate::Result;
+use crate::UIAutomation;
+use crate::UIElement;
+
+use super::CustomActiveTextPositionChangedEventHandler;
+use super::CustomChangesEventHandler;
+use super::CustomEventHandler;
+use super::CustomFocusChangedEventHandler;
+use super::CustomNotificationEventHandler;
+use super::CustomPropertyChangedEventHandler;
+use super::CustomStructureChangedEventHandler;
+use super::CustomTextEditTextChangedEventHandler;
+use super::MtaObject;
+use super::UIActiveTextPositionChangedEventHandler;
+use super::UIChangeInfo;
+use super::UIChangesEventHandler;
+use super::UIEvent;
+use super::UIEventHandler;
+use super::UIEventType;
+use super::UIFocusChangedEventHandler;
+use super::UINotificationEventHandler;
+use super::UIPropertyChangedEventHandler;
+use super::UIStructureChangeEventHandler;
+use super::UITextEditTextChangedEventHandler;
+use super::is_handling_event;
+
+/// Describes the events to listen for by `UIAutomation::add_event_handler()`.
+#[derive(Debug, Clone)]
+pub enum EventSpec {
+    /// The Microsoft UI Automation events of `event_type`, raised by `element` or the elements in `scope`.
+    Automation {
+        event_type: UIEventType,
+        element: UIElement,
+        scope: TreeScope
+    },
+    /// The property-changed events of `properties`, raised by `element` or the elements in `scope`.
+    PropertyChanged {
+        element: UIElement,
+        scope: TreeScope,
+        properties: Vec<UIProperty>
+    },
+    /// The structure-changed events, raised by `element` or the elements in `scope`.
+    StructureChanged {
+        element: UIElement,
+        scope: TreeScope
+    },
+    /// The focus-changed events of the whole desktop.
+    FocusChanged,
+    /// The notification events, raised by `element` or the elements in `scope`.
+    Notification {
+        element: UIElement,
+        scope: TreeScope
+    },
+    /// The change events of `change_types`, raised by `element` or the elements in `scope`.
+    Changes {
+        element: UIElement,
+        scope: TreeScope,
+        change_types: Vec<i32>
+    },
+    /// The text-edit events of `change_type`, raised by `element` or the elements in `scope`.
+    TextEditTextChanged {
+        element: UIElement,
+        scope: TreeScope,
+        change_type: TextEditChangeType
+    },
+    /// The active-text-position-changed events, raised by `element` or the elements in `scope`.
+    ActiveTextPositionChanged {
+        element: UIElement,
+        scope: TreeScope
+    }
+}
+
+impl EventSpec {
+    /// Creates a spec of the Microsoft UI Automation events of `event_type`.
+    pub fn automation(event_type: UIEventType, element: &UIElement, scope: TreeScope) -> Self {
+        Self::Automation {
+            event_type,
+            element: element.clone(),
+            scope
+        }
+    }
+
+    /// Creates a spec of the property-changed events of `properties`.
+    pub fn property_changed(element: &UIElement, scope: TreeScope, properties: &[UIProperty]) -> Self {
+        Self::PropertyChanged {
+            element: element.clone(),
+            scope,
+            properties: properties.to_vec()
+        }
+    }
+
+    /// Creates a spec of the structure-changed events.
+    pub fn structure_changed(element: &UIElement, scope: TreeScope) -> Self {
+        Self::StructureChanged {
+            element: element.clone(),
+            scope
+        }
+    }
+
+    /// Creates a spec of the focus-changed events.
+    pub fn focus_changed() -> Self {
+        Self::FocusChanged
+    }
+
+    /// Creates a spec of the notification events.
+    pub fn notification(element: &UIElement, scope: TreeScope) -> Self {
+        Self::Notification {
+            element: element.clone(),
+            scope
+        }
+    }
+
+    /// Creates a spec of the change events of `change_types`.
+    pub fn changes(element: &UIElement, scope: TreeScope, change_types: &[i32]) -> Self {
+        Self::Changes {
+            element: element.clone(),
+            scope,
+            change_types: change_types.to_vec()
+        }
+    }
+
+    /// Creates a spec of the text-edit events of `change_type`.
+    pub fn text_edit_text_changed(element: &UIElement, scope: TreeScope, change_type: TextEditChangeType) -> Self {
+        Self::TextEditTextChanged {
+            element: element.clone(),
+            scope,
+            change_type
+        }
+    }
+
+    /// Creates a spec of the active-text-position-changed events.
+    pub fn active_text_position_changed(element: &UIElement, scope: TreeScope) -> Self {
+        Self::ActiveTextPositionChanged {
+            element: element.clone(),
+            scope
+        }
+    }
+}
+
+/// Defines a handler function for all kinds of `UIEvent`.
+pub type UIEventHandlerFn = dyn Fn(&UIEvent) -> Result<()>;
+
+/// A handler which converts all kinds of events to `UIEvent`.
+pub(crate) struct EventFnHandler {
+    handler: Box<UIEventHandlerFn>
+}
+
+impl EventFnHandler {
+    pub(crate) fn new(handler: Box<UIEventHandlerFn>) -> Self {
+        Self {
+            handler
+        }
+    }
+}
+
+impl CustomEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement, event_type: UIEventType) -> Result<()> {
+        (self.handler)(&UIEvent::Automation { sender: MtaObject::new(sender.clone())?, timestamp: Local::now(), event_type })
+    }
+}
+
+impl CustomPropertyChangedEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement, property: UIProperty, new_value: Variant) -> Result<()> {
+        // the new value is freed after the callback returns, so the event keeps a copy of it.
+        (self.handler)(&UIEvent::PropertyChanged { sender: MtaObject::new(sender.clone())?, timestamp: Local::now(), property, value: MtaObject::new(new_value.get_owned_value()?)? })
+    }
+}
+
+impl CustomStructureChangedEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement, change_type: StructureChangeType, runtime_id: Option<&[i32]>) -> Result<()> {
+        (self.handler)(&UIEvent::StructureChanged { sender: MtaObject::new(sender.clone())?, timestamp: Local::now(), change_type, runtime_id: runtime_id.map(|id| id.to_vec()) })
+    }
+}
+
+impl CustomFocusChangedEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement) -> Result<()> {
+        (self.handler)(&UIEvent::FocusChanged { sender: MtaObject::new(sender.clone())?, timestamp: Local::now() })
+    }
+}
+
+impl CustomNotificationEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement, kind: NotificationKind, processing: NotificationProcessing, display: &str, activity_id: &str) -> Result<()> {
+        (self.handler)(&UIEvent::Notification { sender: MtaObject::new(sender.clone())?, timestamp: Local::now(), kind, processing, display: display.to_string(), activity_id: activity_id.to_string() })
+    }
+}
+
+impl CustomChangesEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement, changes: &[UIChangeInfo]) -> Result<()> {
+        (self.handler)(&UIEvent::Changes { sender: MtaObject::new(sender.clone())?, timestamp: Local::now(), changes: MtaObject::new(changes.to_vec())? })
+    }
+}
+
+impl CustomTextEditTextChangedEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement, change_type: TextEditChangeType, texts: &[String]) -> Result<()> {
+        (self.handler)(&UIEvent::TextEditTextChanged { sender: MtaObject::new(sender.clone())?, timestamp: Local::now(), change_type, texts: texts.to_vec() })
+    }
+}
+
+impl CustomActiveTextPositionChangedEventHandler for EventFnHandler {
+    fn handle(&self, sender: &UIElement, range: Option<UITextRange>) -> Result<()> {
+        (self.handler)(&UIEvent::ActiveTextPositionChanged { sender: MtaObject::new(sender.clone())?, timestamp: Local::now(), range: range.map(MtaObject::new).transpose()? })
+    }
+}
+
+/// A registered event handler, which can be removed later.
+pub(crate) enum Registration {
+    Automation(UIEventType, UIElement, UIEventHandler),
+    PropertyChanged(UIElement, UIPropertyChangedEventHandler),
+    StructureChanged(UIElement, UIStructureChangeEventHandler),
+    FocusChanged(UIFocusChangedEventHandler),
+    Notification(UIElement, UINotificationEventHandler),
+    Changes(UIElement, UIChangesEventHandler),
+    TextEditTextChanged(UIElement, UITextEditTextChangedEventHandler),
+    ActiveTextPositionChanged(UIElement, UIActiveTextPositionChangedEventHandler)
+}
+
+impl Registration {
+    fn remove(&self, automation: &UIAutomation) -> Result<()> {
+        match self {
+            Self::Automation(event_type, element, handler) => automation.remove_automation_event_handler(*event_type, element, handler),
+            Self::PropertyChanged(element, handler) => automation.remove_property_changed_event_handler(element, handler),
+            Self::StructureChanged(element, handler) => automation.remove_structure_changed_event_handler(element, handler),
+            Self::FocusChanged(handler) => automation.remove_focus_changed_event_handler(handler),
+            Self::Notification(element, handler) => automation.remove_notification_event_handler(element, handler),
+            Self::Changes(element, handler) => automation.remove_changes_event_handler(element, handler),
+            Self::TextEditTextChanged(element, handler) => automation.remove_text_edit_text_changed_event_handler(element, handler),
+            Self::ActiveTextPositionChanged(element, handler) => automation.remove_active_text_position_changed_event_handler(element, handler)
+        }
+    }
+}
+
+/// A registration whose objects are held by agile references, so it can be removed on any thread.
+enum AgileRegistration {
+    Automation(UIEventType, AgileReference<IUIAutomationElement>, AgileReference<IUIAutomationEventHandler>),
+    PropertyChanged(AgileReference<IUIAutomationElement>, AgileReference<IUIAutomationPropertyChangedEventHandler>),
+    StructureChanged(AgileReference<IUIAutomationElement>, AgileReference<IUIAutomationStructureChangedEventHandler>),
+    FocusChanged(AgileReference<IUIAutomationFocusChangedEventHandler>),
+    Notification(AgileReference<IUIAutomationElement>, AgileReference<IUIAutomationNotificationEventHandler>),
+    Changes(AgileReference<IUIAutomationElement>, AgileReference<IUIAutomationChangesEventHandler>),
+    TextEditTextChanged(AgileReference<IUIAutomationElement>, AgileReference<IUIAutomationTextEditTextChangedEventHandler>),
+    ActiveTextPositionChanged(AgileReference<IUIAutomationElement>, AgileReference<IUIAutomationActiveTextPositionChangedEventHandler>)
+}
+
+impl AgileRegistration {
+    fn new(registration: &Registration) -> Result<Self> {
+        let registration = match registration {
+            Registration::Automation(event_type, element, handler) => Self::Automation(*event_type, agile(element)?, agile(handler)?),
+            Registration::PropertyChanged(element, handler) => Self::PropertyChanged(agile(element)?, agile(handler)?),
+            Registration::StructureChanged(element, handler) => Self::StructureChanged(agile(element)?, agile(handler)?),
+            Registration::FocusChanged(handler) => Self::FocusChanged(agile(handler)?),
+            Registration::Notification(element, handler) => Self::Notification(agile(element)?, agile(handler)?),
+            Registration::Changes(element, handler) => Self::Changes(agile(element)?, agile(handler)?),
+            Registration::TextEditTextChanged(element, handler) => Self::TextEditTextChanged(agile(element)?, agile(handler)?),
+            Registration::ActiveTextPositionChanged(element, handler) => Self::ActiveTextPositionChanged(agile(element)?, agile(handler)?)
+        };
+        Ok(registration)
+    }
+
+    /// Resolves the objects in the current apartment, and removes the registration.
+    fn remove(&self, automation: &AgileReference<IUIAutomation>) -> Result<()> {
+        let automation = UIAutomation::from(automation.resolve()?);
+        let registration = match self {
+            Self::Automation(event_type, element, handler) => Registration::Automation(*event_type, element.resolve()?.into(), handler.resolve()?.into()),
+            Self::PropertyChanged(element, handler) => Registration::PropertyChanged(element.resolve()?.into(), handler.resolve()?.into()),
+            Self::StructureChanged(element, handler) => Registration::StructureChanged(element.resolve()?.into(), handler.resolve()?.into()),
+            Self::FocusChanged(handler) => Registration::FocusChanged(handler.resolve()?.into()),
+            Self::Notification(element, handler) => Registration::Notification(element.resolve()?.into(), handler.resolve()?.into()),
+            Self::Changes(element, handler) => Registration::Changes(element.resolve()?.into(), handler.resolve()?.into()),
+            Self::TextEditTextChanged(element, handler) => Registration::TextEditTextChanged(element.resolve()?.into(), handler.resolve()?.into()),
+            Self::ActiveTextPositionChanged(element, handler) => Registration::ActiveTextPositionChanged(element.resolve()?.into(), handler.resolve()?.into())
+        };
+        registration.remove(&automation)
+    }
+}
+
+fn agile<T: Interface>(object: &impl AsRef<T>) -> Result<AgileReference<T>> {
+    Ok(AgileReference::new(object.as_ref())?)
+}
+
+/// A guard of a registered event handler, which removes exactly that registration when it is dropped.
+///
+/// The guard can be dropped on any thread, including the event handler threads.
+/// The automation, the element and the handler are held by agile references, so they are removed in the apartment they belong to.
+/// Removing a handler blocks until its running callbacks return, so the registration is removed on a background thread when it is dropped in an event handler.
+///
+/// Call `detach()` to keep the handler registered until `UIAutomation::remove_all_event_handlers()` is called.
+#[must_use = "the handler is removed when the registration is dropped"]
+pub struct EventRegistration {
+    automation: AgileReference<IUIAutomation>,
+    registration: Option<AgileRegistration>
+}
+
+impl EventRegistration {
+    /// Guards the registered `registration`, or removes it at once if its objects can not be held by agile references.
+    pub(crate) fn new(automation: &UIAutomation, registration: Registration) -> Result<Self> {
+        let guard = agile(automation).and_then(|agile_automation| Ok(Self {
+            automation: agile_automation,
+            registration: Some(AgileRegistration::new(&registration)?)
+        }));
+        if guard.is_err() {
+            let _ = registration.remove(automation);
+        }
+        guard
+    }
+
+    /// Registers `handler` for the events described by `spec`.
+    pub(crate) fn register(automation: &UIAutomation, spec: &EventSpec, handler: Box<UIEventHandlerFn>) -> Result<Self> {
+        let handler = EventFnHandler::new(handler);
+        match spec {
+            EventSpec::Automation { event_type, element, scope } => {
+                automation.add_automation_event_handler(*event_type, element, *scope, None, &UIEventHandler::from(handler))
+            },
+            EventSpec::PropertyChanged { element, scope, properties } => {
+                automation.add_property_changed_event_handler(element, *scope, None, &UIPropertyChangedEventHandler::from(handler), properties)
+            },
+            EventSpec::StructureChanged { element, scope } => {
+                automation.add_structure_changed_event_handler(element, *scope, None, &UIStructureChangeEventHandler::from(handler))
+            },
+            EventSpec::FocusChanged => {
+                automation.add_focus_changed_event_handler(None, &UIFocusChangedEventHandler::from(handler))
+            },
+            EventSpec::Notification { element, scope } => {
+                automation.add_notification_event_handler(element, *scope, None, &UINotificationEventHandler::from(handler))
+            },
+            EventSpec::Changes { element, scope, change_types } => {
+                automation.add_changes_event_handler(element, *scope, change_types, None, &UIChangesEventHandler::from(handler))
+            },
+            EventSpec::TextEditTextChanged { element, scope, change_type } => {
+                automation.add_text_edit_text_changed_event_handler(element, *scope, *change_type, None, &UITextEditTextChangedEventHandler::from(handler))
+            },
+            EventSpec::ActiveTextPositionChanged { element, scope } => {
+                automation.add_active_text_position_changed_event_handler(element, *scope, None, &UIActiveTextPositionChangedEventHandler::from(handler))
+            }
+        }
+    }
+
+    /// Removes the event handler now, and returns an error if it can not be removed.
+    ///
+    /// It blocks until the running callbacks of the handler return, so it should not be called in the handler itself.
+    pub fn remove(mut self) -> Result<()> {
+        match self.registration.take() {
+            Some(registration) => registration.remove(&self.automation),
+            None => Ok(())
+        }
+    }
+
+    /// Keeps the event handler registered after the guard is dropped.
+    pub fn detach(mut self) {
+        self.registration = None;
+    }
+}
+
+impl Drop for EventRegistration {
+    fn drop(&mut self) {
+        let Some(registration) = self.registration.take() else {
+            return;
+        };
+
+        if is_handling_event() {
+            let guard = EventRegistration {
+                automation: self.automation.clone(),
+                registration: Some(registration)
+            };
+            std::thread::spawn(move || {
+                // `S_FALSE` is a success as well, which must be balanced by `CoUninitialize()` too.
+                let initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.is_ok();
+                drop(guard);
+                if initialized {
+                    unsafe { CoUninitialize() };
+                }
+            });
+        } else {
+            let _ = registration.remove(&self.automation);
+        }
+    }
+}
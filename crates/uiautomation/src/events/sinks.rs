@@ -0,0 +1,183 @@
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::mpsc::Sender;
+
+use crate::errors::ERR_PANIC;
+use crate::log_error;
+use crate::Error;
+use crate::Result;
+use crate::UIElement;
+
+use super::MtaObject;
+use super::UIEventType;
+
+/// The error sink function, which receives the errors and panics of the event handlers.
+pub type ErrorSinkFn = dyn Fn(&HandlerError) + Send + Sync;
+
+static ERROR_SINK: RwLock<Option<Arc<ErrorSinkFn>>> = RwLock::new(None);
+
+/// An error returned by an event handler, or a panic caught in it, with the event it is handling.
+pub struct HandlerError {
+    event_id: i32,
+    sender: Option<MtaObject<UIElement>>,
+    error: Error
+}
+
+impl HandlerError {
+    /// Retrieves the raw id of the handled event.
+    pub fn get_event_id(&self) -> i32 {
+        self.event_id
+    }
+
+    /// Retrieves the type of the handled event, or `None` if it is not a known event type.
+    pub fn get_event_type(&self) -> Option<UIEventType> {
+        UIEventType::try_from(self.event_id).ok()
+    }
+
+    /// Retrieves the element which raises the event, if it is provided.
+    ///
+    /// The sender is not kept if the handler is called out of the multithreaded apartment, since the error can be sent to other threads.
+    pub fn get_sender(&self) -> Option<&UIElement> {
+        self.sender.as_deref()
+    }
+
+    /// Retrieves the error. A caught panic is an `ERR_PANIC` error carrying the panic message.
+    pub fn get_error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Checks whether the handler panicked.
+    pub fn is_panic(&self) -> bool {
+        self.error.code() == ERR_PANIC
+    }
+}
+
+impl Clone for HandlerError {
+    fn clone(&self) -> Self {
+        Self {
+            event_id: self.event_id,
+            sender: self.sender.clone(),
+            error: Error::new(self.error.code(), self.error.message())
+        }
+    }
+}
+
+impl std::fmt::Debug for HandlerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandlerError")
+            .field("event_type", &self.get_event_type().map(|t| t.to_string()).unwrap_or_else(|| self.event_id.to_string()))
+            .field("sender", &self.sender.as_ref().map(|s| s.get_name().unwrap_or_default()))
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl std::fmt::Display for HandlerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.get_event_type() {
+            Some(event_type) => write!(f, "{} handler failed: {}", event_type, self.error),
+            None => write!(f, "event {} handler failed: {}", self.event_id, self.error)
+        }
+    }
+}
+
+/// Sets the error sink, which receives the errors returned by the event handlers and the panics caught in them.
+///
+/// The sink is process-wide and replaces the previous one. It is called on the thread running the handler, so it should return quickly.
+/// Without a sink, the panics are logged and the errors are only returned to UI Automation.
+pub fn set_error_sink<F>(sink: F) where F: Fn(&HandlerError) + Send + Sync + 'static {
+    if let Ok(mut current) = ERROR_SINK.write() {
+        *current = Some(Arc::new(sink));
+    }
+}
+
+/// Sets the error sink to a channel, which receives the errors and panics of the event handlers.
+pub fn set_error_channel(sender: Sender<HandlerError>) {
+    set_error_sink(move |error: &HandlerError| {
+        let _ = sender.send(error.clone());
+    });
+}
+
+/// Removes the error sink.
+pub fn clear_error_sink() {
+    if let Ok(mut current) = ERROR_SINK.write() {
+        *current = None;
+    }
+}
+
+/// Runs the handler `f` for the event `event_id`, catches its panic, and reports the failure to the error sink.
+pub(crate) fn guard_handler<S, F>(event_id: i32, sender: S, f: F) -> Result<()> where S: FnOnce() -> Option<UIElement>, F: FnOnce() -> Result<()> {
+    let error = match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(e)) => e,
+        Err(payload) => Error::new(ERR_PANIC, &panic_message(payload.as_ref()))
+    };
+
+    let sink = ERROR_SINK.read().ok().and_then(|s| s.clone());
+    let panicked = error.code() == ERR_PANIC;
+    let failure = HandlerError {
+        event_id,
+        sender: sender().and_then(|sender| MtaObject::new(sender).ok()),
+        error
+    };
+
+    match sink {
+        Some(sink) if std::panic::catch_unwind(AssertUnwindSafe(|| sink(&failure))).is_err() => {
+            log_error!("error sink panicked on: {}", failure);
+        },
+        Some(_) => {},
+        None if panicked => {
+            log_error!("{}", failure);
+        },
+        None => {}
+    }
+
+    Err(failure.error)
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("event handler panicked: {}", message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("event handler panicked: {}", message)
+    } else {
+        String::from("event handler panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::errors::ERR_NOTFOUND;
+    use crate::errors::ERR_PANIC;
+    use crate::Error;
+
+    use super::UIEventType;
+    use super::clear_error_sink;
+    use super::guard_handler;
+    use super::set_error_channel;
+
+    #[test]
+    fn test_error_sink() {
+        let (sender, receiver) = mpsc::channel();
+        set_error_channel(sender);
+
+        let event_id = UIEventType::AutomationFocusChanged as i32;
+        assert!(guard_handler(event_id, || None, || Ok(())).is_ok());
+        assert_eq!(guard_handler(event_id, || None, || Err(Error::new(ERR_NOTFOUND, "not found"))).unwrap_err().code(), ERR_NOTFOUND);
+        assert_eq!(guard_handler(event_id, || None, || panic!("boom")).unwrap_err().code(), ERR_PANIC);
+        clear_error_sink();
+
+        // the failures of other handlers running in parallel always have a sender.
+        let failures: Vec<_> = receiver.try_iter().filter(|f| f.get_sender().is_none()).collect();
+        assert_eq!(failures.len(), 2);
+        assert!(!failures[0].is_panic());
+        assert!(failures[1].is_panic());
+        assert_eq!(failures[1].get_event_type(), Some(UIEventType::AutomationFocusChanged));
+        assert!(failures[1].get_error().message().contains("boom"));
+        println!("{}", failures[1]);
+    }
+}
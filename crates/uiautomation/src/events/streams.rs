@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+use futures_core::Stream;
+
+use crate::errors::ERR_OVERFLOW;
+use crate::Error;
+use crate::Result;
+use crate::UIAutomation;
+
+use super::EventRegistration;
+use super::EventSpec;
+use super::UIEvent;
+
+/// Defines what to do when the buffer of an `EventStream` is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Drops the oldest buffered event to make room for the new one.
+    #[default]
+    DropOldest,
+    /// Blocks the UI Automation event thread until the stream is polled.
+    ///
+    /// No other events of the same client are delivered while blocking, so the stream should be polled timely.
+    Block,
+    /// Stops the stream with an overflow error.
+    ///
+    /// The buffered events are still yielded first, then the stream yields `Err` with `ERR_OVERFLOW` once and ends.
+    Error
+}
+
+struct BufferState {
+    events: VecDeque<UIEvent>,
+    waker: Option<Waker>,
+    dropped: usize,
+    error: Option<Error>,
+    error_yielded: bool,
+    closed: bool
+}
+
+/// A bounded event buffer shared by the event handler and the stream.
+struct EventBuffer {
+    capacity: usize,
+    policy: OverflowPolicy,
+    state: Mutex<BufferState>,
+    space: Condvar
+}
+
+impl EventBuffer {
+    fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            policy,
+            state: Mutex::new(BufferState {
+                events: VecDeque::with_capacity(capacity.clamp(1, 1024)),
+                waker: None,
+                dropped: 0,
+                error: None,
+                error_yielded: false,
+                closed: false
+            }),
+            space: Condvar::new()
+        }
+    }
+
+    fn push(&self, event: UIEvent) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+
+        while !state.closed && state.error.is_none() && state.events.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    state.events.pop_front();
+                    state.dropped += 1;
+                },
+                OverflowPolicy::Block => {
+                    state = match self.space.wait(state) {
+                        Ok(state) => state,
+                        Err(_) => return
+                    };
+                },
+                OverflowPolicy::Error => {
+                    state.error = Some(Error::new(ERR_OVERFLOW, "event buffer overflow"));
+                }
+            }
+        }
+
+        if state.closed {
+            return;
+        }
+        if state.error.is_none() {
+            state.events.push_back(event);
+        }
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<Result<UIEvent>>> {
+        let Ok(mut state) = self.state.lock() else {
+            return Poll::Ready(None);
+        };
+
+        if let Some(event) = state.events.pop_front() {
+            self.space.notify_one();
+            Poll::Ready(Some(Ok(event)))
+        } else if let Some(ref error) = state.error && !state.error_yielded {
+            let error = Error::new(error.code(), error.message());
+            state.error_yielded = true;
+            Poll::Ready(Some(Err(error)))
+        } else if state.error.is_some() || state.closed {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn close(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+        }
+        self.space.notify_all();
+    }
+}
+
+/// An asynchronous stream of UI Automation events, created by `UIAutomation::event_stream()`.
+///
+/// The events are buffered up to the capacity of the stream, and the `OverflowPolicy` decides what happens when the buffer is full.
+/// The stream only yields `Err` for the overflow of `OverflowPolicy::Error`, after which it ends.
+/// The stream does not depend on any async runtime. The event handler is removed when the stream is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::UIAutomation;
+/// use uiautomation::events::EventSpec;
+/// use uiautomation::events::EventStream;
+/// use uiautomation::events::OverflowPolicy;
+/// use uiautomation::events::UIEventType;
+/// use uiautomation::types::TreeScope;
+///
+/// fn window_opened() -> uiautomation::Result<EventStream> {
+///     let automation = UIAutomation::new()?;
+///     let root = automation.get_root_element()?;
+///     let spec = EventSpec::automation(UIEventType::Window_WindowOpened, &root, TreeScope::Subtree);
+///     automation.event_stream_with(&spec, 64, OverflowPolicy::DropOldest)
+/// }
+/// ```
+pub struct EventStream {
+    buffer: Arc<EventBuffer>,
+    registration: Option<EventRegistration>
+}
+
+impl EventStream {
+    pub(crate) fn new(automation: &UIAutomation, spec: &EventSpec, capacity: usize, policy: OverflowPolicy) -> Result<Self> {
+        let buffer = Arc::new(EventBuffer::new(capacity, policy));
+        let handler_buffer = buffer.clone();
+        let registration = EventRegistration::register(automation, spec, Box::new(move |event| {
+            handler_buffer.push(event.clone());
+            Ok(())
+        }))?;
+
+        Ok(Self {
+            buffer,
+            registration: Some(registration)
+        })
+    }
+
+    /// Retrieves the capacity of the buffer.
+    pub fn get_capacity(&self) -> usize {
+        self.buffer.capacity
+    }
+
+    /// Retrieves the overflow policy.
+    pub fn get_policy(&self) -> OverflowPolicy {
+        self.buffer.policy
+    }
+
+    /// Retrieves the count of the events dropped by `OverflowPolicy::DropOldest`.
+    pub fn get_dropped_count(&self) -> usize {
+        self.buffer.state.lock().map(|s| s.dropped).unwrap_or_default()
+    }
+
+    /// Retrieves the overflow error which stopped the stream, by `OverflowPolicy::Error`.
+    pub fn get_error(&self) -> Option<Error> {
+        self.buffer.state.lock().ok().and_then(|s| s.error.as_ref().map(|e| Error::new(e.code(), e.message())))
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<UIEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.buffer.poll_next(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        // wakes up the blocked handlers before removing them, which waits for the running handlers.
+        self.buffer.close();
+        self.registration.take();
+    }
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("capacity", &self.buffer.capacity)
+            .field("policy", &self.buffer.policy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Waker;
+
+    use chrono::Local;
+
+    use crate::errors::ERR_OVERFLOW;
+    use crate::UIAutomation;
+    use crate::events::MtaObject;
+
+    use super::EventBuffer;
+    use super::OverflowPolicy;
+    use super::UIEvent;
+
+    fn focus_event(automation: &UIAutomation) -> UIEvent {
+        UIEvent::FocusChanged {
+            sender: MtaObject::new(automation.get_root_element().unwrap()).unwrap(),
+            timestamp: Local::now()
+        }
+    }
+
+    #[test]
+    fn test_overflow_policy() {
+        let automation = UIAutomation::new().unwrap();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let buffer = EventBuffer::new(2, OverflowPolicy::DropOldest);
+        for _ in 0..3 {
+            buffer.push(focus_event(&automation));
+        }
+        assert_eq!(buffer.state.lock().unwrap().dropped, 1);
+        assert!(matches!(buffer.poll_next(&mut cx), Poll::Ready(Some(Ok(_)))));
+        assert!(matches!(buffer.poll_next(&mut cx), Poll::Ready(Some(Ok(_)))));
+        assert!(buffer.poll_next(&mut cx).is_pending());
+
+        let buffer = EventBuffer::new(1, OverflowPolicy::Error);
+        buffer.push(focus_event(&automation));
+        buffer.push(focus_event(&automation));
+        assert!(buffer.state.lock().unwrap().error.is_some());
+        assert!(matches!(buffer.poll_next(&mut cx), Poll::Ready(Some(Ok(_)))));
+        match buffer.poll_next(&mut cx) {
+            Poll::Ready(Some(Err(e))) => assert_eq!(e.code(), ERR_OVERFLOW),
+            _ => panic!("expect an overflow error")
+        }
+        assert!(matches!(buffer.poll_next(&mut cx), Poll::Ready(None)));
+    }
+
+    #[test]
+    fn test_event_stream() {
+        use std::pin::Pin;
+
+        use futures_core::Stream;
+
+        use crate::events::EventSpec;
+
+        let automation = UIAutomation::new().unwrap();
+        let mut stream = automation.event_stream_with(&EventSpec::focus_changed(), 16, OverflowPolicy::Block).unwrap();
+        let mut cx = Context::from_waker(Waker::noop());
+        while let Poll::Ready(Some(event)) = Pin::new(&mut stream).poll_next(&mut cx) {
+            println!("{:?}", event);
+        }
+        println!("{:?}", stream);
+    }
+}
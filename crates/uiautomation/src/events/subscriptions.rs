@@ -0,0 +1,77 @@
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::TryRecvError;
+use std::time::Duration;
+
+use crate::Result;
+
+use super::EventRegistration;
+use super::UIEvent;
+
+/// An event subscription which receives the events from a channel.
+///
+/// The events are sent by the UI Automation threads, and can be received on any thread.
+/// The event handler is removed when the subscription is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use uiautomation::UIAutomation;
+/// use uiautomation::events::UIEventType;
+/// use uiautomation::types::TreeScope;
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let root = automation.get_root_element().unwrap();
+/// let subscription = automation.subscribe(UIEventType::Window_WindowOpened, &root, TreeScope::Subtree).unwrap();
+///
+/// std::thread::spawn(move || {
+///     while let Ok(event) = subscription.recv_timeout(Duration::from_secs(10)) {
+///         println!("{:?}", event);
+///     }
+/// });
+/// ```
+pub struct Subscription {
+    registration: EventRegistration,
+    receiver: Receiver<UIEvent>
+}
+
+impl Subscription {
+    pub(crate) fn new(registration: EventRegistration, receiver: Receiver<UIEvent>) -> Self {
+        Self {
+            registration,
+            receiver
+        }
+    }
+
+    /// Retrieves the receiver of the events.
+    pub fn get_receiver(&self) -> &Receiver<UIEvent> {
+        &self.receiver
+    }
+
+    /// Waits for the next event.
+    pub fn recv(&self) -> Option<UIEvent> {
+        self.receiver.recv().ok()
+    }
+
+    /// Waits for the next event at most `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> std::result::Result<UIEvent, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+
+    /// Receives an event without waiting.
+    pub fn try_recv(&self) -> std::result::Result<UIEvent, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    /// Returns an iterator which waits for the events.
+    pub fn iter(&self) -> std::sync::mpsc::Iter<'_, UIEvent> {
+        self.receiver.iter()
+    }
+
+    /// Removes the event handler, and returns an error if it can not be removed.
+    pub fn unsubscribe(self) -> Result<()> {
+        self.registration.remove()
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::errors::ERR_TIMEOUT;
+use crate::processes::ProcessSnapshot;
+use crate::types::Handle;
+use crate::types::TreeScope;
+use crate::Error;
+use crate::Result;
+use crate::UIAutomation;
+use crate::UIElement;
+
+use super::EventRegistration;
+use super::EventSpec;
+use super::MtaObject;
+use super::UIEvent;
+use super::UIEventType;
+
+/// The count of the recently opened windows kept for `WindowTracker::wait_for_window()`, including the closed ones.
+const RECENT_WINDOWS: usize = 256;
+
+/// The time to remember a process id which does not belong to the tracked process, since the id may be reused by a new process.
+const OTHER_PROCESS_EXPIRY: Duration = Duration::from_secs(5);
+
+/// A window tracked by `WindowTracker`.
+#[derive(Clone)]
+pub struct TrackedWindow {
+    pub element: MtaObject<UIElement>,
+    pub runtime_id: Vec<i32>,
+    pub process_id: u32,
+    pub handle: Handle,
+    pub name: String,
+    pub classname: String
+}
+
+impl TrackedWindow {
+    fn new(element: &MtaObject<UIElement>, process_id: u32) -> Self {
+        Self {
+            element: element.clone(),
+            runtime_id: element.get_runtime_id().unwrap_or_default(),
+            process_id,
+            handle: element.get_native_window_handle().unwrap_or_default(),
+            name: element.get_name().unwrap_or_default(),
+            classname: element.get_classname().unwrap_or_default()
+        }
+    }
+}
+
+impl std::fmt::Debug for TrackedWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrackedWindow")
+            .field("runtime_id", &self.runtime_id)
+            .field("process_id", &self.process_id)
+            .field("handle", &self.handle)
+            .field("name", &self.name)
+            .field("classname", &self.classname)
+            .finish()
+    }
+}
+
+type WindowHandlerFn = dyn Fn(&TrackedWindow) + Send + Sync;
+
+/// The process ids known to belong to the tracked process or not.
+struct ProcessIds {
+    pid: u32,
+    sub_processes: bool,
+    members: Vec<u32>,
+    others: HashMap<u32, Instant>
+}
+
+impl ProcessIds {
+    fn contains(&mut self, pid: u32) -> bool {
+        if self.members.contains(&pid) {
+            return true;
+        }
+        if !self.sub_processes {
+            return false;
+        }
+
+        let now = Instant::now();
+        self.others.retain(|_, checked| now.duration_since(*checked) < OTHER_PROCESS_EXPIRY);
+        if self.others.contains_key(&pid) {
+            return false;
+        }
+
+        // the sub processes may be started after the last snapshot.
+        if let Ok(snapshot) = ProcessSnapshot::new() {
+            self.members = snapshot.with_sub_processes(&[self.pid]);
+        }
+        if self.members.contains(&pid) {
+            true
+        } else {
+            self.others.insert(pid, now);
+            false
+        }
+    }
+}
+
+struct TrackerWindows {
+    windows: Vec<TrackedWindow>,
+    recent: VecDeque<(u64, TrackedWindow)>,
+    sequence: u64
+}
+
+struct TrackerState {
+    process_ids: Mutex<ProcessIds>,
+    windows: Mutex<TrackerWindows>,
+    changed: Condvar,
+    opened_handlers: Mutex<Vec<Arc<WindowHandlerFn>>>,
+    closed_handlers: Mutex<Vec<Arc<WindowHandlerFn>>>
+}
+
+impl TrackerState {
+    fn contains_process(&self, pid: u32) -> bool {
+        self.process_ids.lock().map(|mut ids| ids.contains(pid)).unwrap_or(false)
+    }
+
+    fn on_opened(&self, element: &MtaObject<UIElement>) {
+        let Ok(pid) = element.get_process_id() else {
+            return;
+        };
+        if !self.contains_process(pid) {
+            return;
+        }
+
+        let window = TrackedWindow::new(element, pid);
+        if let Ok(mut windows) = self.windows.lock() {
+            if !window.runtime_id.is_empty() && windows.windows.iter().any(|w| w.runtime_id == window.runtime_id) {
+                return;
+            }
+
+            windows.sequence += 1;
+            let sequence = windows.sequence;
+            windows.windows.push(window.clone());
+            windows.recent.push_back((sequence, window.clone()));
+            if windows.recent.len() > RECENT_WINDOWS {
+                windows.recent.pop_front();
+            }
+        }
+        self.changed.notify_all();
+
+        Self::notify(&self.opened_handlers, &window);
+    }
+
+    fn on_closed(&self, element: &UIElement) {
+        // the closed window may not be available any more, so the windows which can not be accessed are removed too.
+        // they are probed without holding the lock, since the probing is a cross-process call.
+        let runtime_id = element.get_runtime_id().unwrap_or_default();
+        let Ok(candidates) = self.windows.lock().map(|w| w.windows.clone()) else {
+            return;
+        };
+        let closed_elements: Vec<MtaObject<UIElement>> = candidates.into_iter()
+            .filter(|w| (!runtime_id.is_empty() && w.runtime_id == runtime_id) || w.element.get_process_id().is_err())
+            .map(|w| w.element)
+            .collect();
+        if closed_elements.is_empty() {
+            return;
+        }
+
+        let closed: Vec<TrackedWindow> = match self.windows.lock() {
+            Ok(mut windows) => {
+                let (closed, alive) = windows.windows.drain(..).partition(|w| closed_elements.contains(&w.element));
+                windows.windows = alive;
+                closed
+            },
+            Err(_) => return
+        };
+        self.changed.notify_all();
+
+        for window in closed.iter() {
+            Self::notify(&self.closed_handlers, window);
+        }
+    }
+
+    fn notify(handlers: &Mutex<Vec<Arc<WindowHandlerFn>>>, window: &TrackedWindow) {
+        let handlers = handlers.lock().map(|h| h.clone()).unwrap_or_default();
+        for handler in handlers {
+            handler(window);
+        }
+    }
+}
+
+/// Tracks the windows of a process, including its sub processes optionally.
+///
+/// The tracker listens for the `Window_WindowOpened` and `Window_WindowClosed` events in the whole desktop, and keeps a live list of the windows.
+/// So the nested windows which raise the events, such as the dialogs shown inside their owner windows, are tracked as well as the top-level windows.
+/// The short-lived windows, such as the dialogs closed soon, are also reported to the callbacks and `wait_for_window()`.
+/// The event handlers are removed when the tracker is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::UIAutomation;
+/// use uiautomation::events::WindowTracker;
+/// use uiautomation::processes::Process;
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let process = Process::create("notepad.exe").unwrap();
+/// let tracker = WindowTracker::new(&automation, process.get_id(), true).unwrap();
+/// tracker.on_opened(|window| println!("opened: {:?}", window));
+///
+/// let window = tracker.wait_for_window(|w| w.classname == "Notepad", 10000).unwrap();
+/// println!("{}", window.name);
+/// ```
+pub struct WindowTracker {
+    state: Arc<TrackerState>,
+    registrations: Vec<EventRegistration>
+}
+
+impl WindowTracker {
+    /// Starts tracking the windows of the process `pid`, and its sub processes if `sub_processes` is `true`.
+    ///
+    /// The top-level windows which are already opened are added to the list.
+    /// It must be called in the multithreaded apartment, such as the threads calling `UIAutomation::new()`, since the windows are shared with the event threads.
+    pub fn new(automation: &UIAutomation, pid: u32, sub_processes: bool) -> Result<Self> {
+        let members = if sub_processes {
+            ProcessSnapshot::new()?.with_sub_processes(&[pid])
+        } else {
+            vec![pid]
+        };
+
+        let state = Arc::new(TrackerState {
+            process_ids: Mutex::new(ProcessIds {
+                pid,
+                sub_processes,
+                members,
+                others: HashMap::new()
+            }),
+            windows: Mutex::new(TrackerWindows {
+                windows: Vec::new(),
+                recent: VecDeque::new(),
+                sequence: 0
+            }),
+            changed: Condvar::new(),
+            opened_handlers: Mutex::new(Vec::new()),
+            closed_handlers: Mutex::new(Vec::new())
+        });
+
+        let root = MtaObject::new(automation.get_root_element()?)?;
+        let mut registrations = Vec::with_capacity(2);
+        for event_type in [UIEventType::Window_WindowOpened, UIEventType::Window_WindowClosed] {
+            let handler_state = state.clone();
+            let registration = automation.add_event_handler(&EventSpec::automation(event_type, &root, TreeScope::Subtree), move |event| {
+                if let UIEvent::Automation { sender, event_type, .. } = event {
+                    match event_type {
+                        UIEventType::Window_WindowOpened => handler_state.on_opened(sender),
+                        _ => handler_state.on_closed(sender)
+                    }
+                }
+                Ok(())
+            })?;
+            registrations.push(registration);
+        }
+
+        let walker = automation.get_control_view_walker()?;
+        let mut child = walker.get_first_child(&root).ok();
+        while let Some(element) = child {
+            let window = MtaObject::new(element)?;
+            state.on_opened(&window);
+            child = walker.get_next_sibling(&window).ok();
+        }
+
+        Ok(Self {
+            state,
+            registrations
+        })
+    }
+
+    /// Retrieves the windows which are opened now.
+    pub fn get_windows(&self) -> Vec<TrackedWindow> {
+        self.state.windows.lock().map(|w| w.windows.clone()).unwrap_or_default()
+    }
+
+    /// Adds a callback for the opened windows.
+    ///
+    /// The callbacks run on the UI Automation event threads, so they should return quickly.
+    pub fn on_opened<F>(&self, handler: F) where F: Fn(&TrackedWindow) + Send + Sync + 'static {
+        if let Ok(mut handlers) = self.state.opened_handlers.lock() {
+            handlers.push(Arc::new(handler));
+        }
+    }
+
+    /// Adds a callback for the closed windows.
+    ///
+    /// The callbacks run on the UI Automation event threads, so they should return quickly.
+    pub fn on_closed<F>(&self, handler: F) where F: Fn(&TrackedWindow) + Send + Sync + 'static {
+        if let Ok(mut handlers) = self.state.closed_handlers.lock() {
+            handlers.push(Arc::new(handler));
+        }
+    }
+
+    /// Waits for a window matched by `filter` at most `timeout` milliseconds.
+    ///
+    /// The opened windows are checked first, then the windows opened during the waiting, even if they are closed before it returns.
+    pub fn wait_for_window<F>(&self, filter: F, timeout: u64) -> Result<TrackedWindow> where F: Fn(&TrackedWindow) -> bool {
+        let deadline = Instant::now() + Duration::from_millis(timeout);
+        let Ok(mut windows) = self.state.windows.lock() else {
+            return Err(Error::new(ERR_TIMEOUT, "wait window time out"));
+        };
+
+        if let Some(window) = windows.windows.iter().find(|w| filter(w)) {
+            return Ok(window.clone());
+        }
+
+        let mut checked = windows.sequence;
+        loop {
+            if let Some((_, window)) = windows.recent.iter().find(|(sequence, window)| *sequence > checked && filter(window)) {
+                return Ok(window.clone());
+            }
+            checked = windows.sequence;
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::new(ERR_TIMEOUT, "wait window time out"));
+            }
+            windows = match self.state.changed.wait_timeout(windows, deadline - now) {
+                Ok((windows, _)) => windows,
+                Err(_) => return Err(Error::new(ERR_TIMEOUT, "wait window time out"))
+            };
+        }
+    }
+
+    /// Stops tracking, and removes the event handlers.
+    pub fn stop(self) -> Result<()> {
+        let mut result = Ok(());
+        for registration in self.registrations {
+            if let Err(e) = registration.remove() && result.is_ok() {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+impl std::fmt::Debug for WindowTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WindowTracker").field("windows", &self.get_windows()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::UIAutomation;
+
+    use super::WindowTracker;
+
+    #[test]
+    fn test_window_tracker() {
+        let automation = UIAutomation::new().unwrap();
+        let tracker = WindowTracker::new(&automation, std::process::id(), true).unwrap();
+        tracker.on_opened(|window| println!("opened: {:?}", window));
+        tracker.on_closed(|window| println!("closed: {:?}", window));
+        println!("{:?}", tracker);
+
+        assert!(tracker.wait_for_window(|_| false, 100).is_err());
+        tracker.stop().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "control")]
+    fn test_track_window() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        use windows::Win32::UI::WindowsAndMessaging::*;
+        use windows::core::w;
+
+        const TITLE: &str = "uiautomation window tracker test";
+
+        let automation = UIAutomation::new().unwrap();
+        let tracker = WindowTracker::new(&automation, std::process::id(), false).unwrap();
+        let (closed_sender, closed_receiver) = mpsc::channel();
+        tracker.on_closed(move |window| {
+            let _ = closed_sender.send(window.name.clone());
+        });
+
+        let (close_sender, close_receiver) = mpsc::channel::<()>();
+        let window_thread = std::thread::spawn(move || unsafe {
+            let hwnd = CreateWindowExW(WINDOW_EX_STYLE::default(), w!("STATIC"), w!("uiautomation window tracker test"), WS_OVERLAPPEDWINDOW | WS_VISIBLE, 0, 0, 300, 200, None, None, None, None).unwrap();
+            let mut msg = MSG::default();
+            while close_receiver.try_recv().is_err() {
+                while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            DestroyWindow(hwnd).unwrap();
+        });
+
+        let window = tracker.wait_for_window(|w| w.name == TITLE, 10000).unwrap();
+        assert_eq!(window.process_id, std::process::id());
+        assert!(tracker.get_windows().iter().any(|w| w.runtime_id == window.runtime_id));
+
+        close_sender.send(()).unwrap();
+        window_thread.join().unwrap();
+        let mut closed = false;
+        while let Ok(name) = closed_receiver.recv_timeout(Duration::from_secs(10)) {
+            if name == TITLE {
+                closed = true;
+                break;
+            }
+        }
+        assert!(closed);
+        assert!(!tracker.get_windows().iter().any(|w| w.runtime_id == window.runtime_id));
+
+        tracker.stop().unwrap();
+    }
+}
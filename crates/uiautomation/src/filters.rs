@@ -1,23 +1,18 @@
-#[cfg(feature = "process")]
 use std::cell::RefCell;
 use std::fmt::Debug;
 
-// use windows::Win32::Foundation::CloseHandle;
-// use windows::Win32::System::Diagnostics::ToolHelp::CreateToolhelp32Snapshot;
-// use windows::Win32::System::Diagnostics::ToolHelp::Process32First;
-// use windows::Win32::System::Diagnostics::ToolHelp::Process32Next;
-// use windows::Win32::System::Diagnostics::ToolHelp::PROCESSENTRY32;
-// use windows::Win32::System::Diagnostics::ToolHelp::TH32CS_SNAPPROCESS;
 #[cfg(feature = "process")]
-use windows::Win32::System::Diagnostics::ToolHelp::{
-    CreateToolhelp32Snapshot, Process32First, Process32Next, PROCESSENTRY32, TH32CS_SNAPPROCESS
-};
+use super::processes::ProcessSnapshot;
 
 // use crate::controls::ControlType;
 
 use super::types::ControlType;
+use super::types::Rect;
+use super::texts::NameNormalizer;
+use super::texts::Similarity;
 
 use super::core::UIElement;
+use super::core::UITreeWalker;
 use super::errors::Result;
 
 /// `MatcherFilter` is an element filter that can be used in `UIMatcher`.
@@ -102,6 +97,39 @@ impl MatcherFilter for NameFilter {
     }
 }
 
+/// Filters the elements whose normalized names are similar to the normalized value.
+#[derive(Debug)]
+pub struct FuzzyNameFilter {
+    value: String,
+    normalizer: NameNormalizer,
+    similarity: Similarity,
+    threshold: f64
+}
+
+impl FuzzyNameFilter {
+    /// Creates a filter matching the names whose similarity to `value` is not less than `threshold` (`0.0` to `1.0`).
+    /// Both names are normalized by `normalizer` before comparing, and a `threshold` of `1.0` requires equal normalized names.
+    pub fn new(value: &str, normalizer: NameNormalizer, similarity: Similarity, threshold: f64) -> Self {
+        Self {
+            value: normalizer.normalize(value),
+            normalizer,
+            similarity,
+            threshold
+        }
+    }
+}
+
+impl MatcherFilter for FuzzyNameFilter {
+    fn judge(&self, element: &UIElement) -> Result<bool> {
+        let name = self.normalizer.normalize(&element.get_name()?);
+        Ok(if self.threshold >= 1.0 {
+            name == self.value
+        } else {
+            self.similarity.score(&name, &self.value) >= self.threshold
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct ClassNameFilter {
     pub classname: String
@@ -127,6 +155,59 @@ impl MatcherFilter for ControlTypeFilter {
     }
 }
 
+/// Filters the elements by the text of their labels.
+///
+/// The label is the `LabeledBy` element. When `LabeledBy` is not set, the label is the nearest preceding `Text` sibling found by `walker`.
+/// The texts are compared ignoring case, surrounding spaces and a trailing colon, so `"User Name"` matches the label `"User name:"`.
+pub struct LabeledFilter {
+    pub label: String,
+    pub walker: Option<UITreeWalker>
+}
+
+impl LabeledFilter {
+    fn normalize(text: &str) -> String {
+        text.trim().trim_end_matches([':', '：']).trim_end().to_lowercase()
+    }
+
+    fn get_label_name(&self, element: &UIElement) -> Result<Option<String>> {
+        if let Ok(label) = element.get_labeled_by() {
+            return Ok(Some(label.get_name()?));
+        }
+
+        let Some(ref walker) = self.walker else {
+            return Ok(None);
+        };
+        if element.get_control_type()? == ControlType::Text {
+            return Ok(None);
+        }
+
+        let mut sibling = walker.get_previous_sibling(element);
+        while let Ok(prev) = sibling {
+            if prev.get_control_type()? == ControlType::Text {
+                return Ok(Some(prev.get_name()?));
+            }
+            sibling = walker.get_previous_sibling(&prev);
+        }
+
+        Ok(None)
+    }
+}
+
+impl Debug for LabeledFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LabeledFilter").field("label", &self.label).finish()
+    }
+}
+
+impl MatcherFilter for LabeledFilter {
+    fn judge(&self, element: &UIElement) -> Result<bool> {
+        Ok(match self.get_label_name(element)? {
+            Some(name) => Self::normalize(&name) == Self::normalize(&self.label),
+            None => false
+        })
+    }
+}
+
 pub struct FnFilter<F> where F: Fn(&UIElement) -> Result<bool> {
     pub filter: Box<F>
 }
@@ -137,6 +218,100 @@ impl<F> MatcherFilter for FnFilter<F> where F: Fn(&UIElement) -> Result<bool> {
     }
 }
 
+/// Defines the spatial relation between an element and an anchor rect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpatialRelation {
+    /// The element is on the right of the anchor and they share some rows.
+    RightOf,
+    /// The element is on the left of the anchor and they share some rows.
+    LeftOf,
+    /// The element is below the anchor and they share some columns.
+    Below,
+    /// The element is above the anchor and they share some columns.
+    Above,
+    /// The distance between the element and the anchor is not more than the pixels. The anchor element itself is not matched by `SpatialFilter`.
+    Near(i32),
+    /// The element is completely inside the anchor.
+    Inside
+}
+
+impl SpatialRelation {
+    /// Checks whether `rect` has this relation to `anchor`.
+    pub fn is_satisfied(&self, rect: &Rect, anchor: &Rect) -> bool {
+        match *self {
+            Self::RightOf => rect.get_left() >= anchor.get_right() && rect.overlaps_vertically(anchor),
+            Self::LeftOf => rect.get_right() <= anchor.get_left() && rect.overlaps_vertically(anchor),
+            Self::Below => rect.get_top() >= anchor.get_bottom() && rect.overlaps_horizontally(anchor),
+            Self::Above => rect.get_bottom() <= anchor.get_top() && rect.overlaps_horizontally(anchor),
+            Self::Near(max_distance) => rect.distance_to(anchor) <= max_distance as f64,
+            Self::Inside => anchor.contains_rect(rect)
+        }
+    }
+}
+
+/// Filters the elements by their bounding rectangles relative to an anchor.
+///
+/// The anchor rect of an anchor element is retrieved once, when the first element is judged.
+pub struct SpatialFilter {
+    pub relation: SpatialRelation,
+    anchor: Option<UIElement>,
+    anchor_rect: RefCell<Option<Rect>>
+}
+
+impl SpatialFilter {
+    /// Creates a filter relative to the bounding rectangle of `anchor`.
+    pub fn new(relation: SpatialRelation, anchor: &UIElement) -> Self {
+        Self {
+            relation,
+            anchor: Some(anchor.clone()),
+            anchor_rect: RefCell::new(None)
+        }
+    }
+
+    /// Creates a filter relative to the fixed `rect`.
+    pub fn from_rect(relation: SpatialRelation, rect: Rect) -> Self {
+        Self {
+            relation,
+            anchor: None,
+            anchor_rect: RefCell::new(Some(rect))
+        }
+    }
+
+    fn get_anchor_rect(&self) -> Result<Rect> {
+        if let Some(rect) = *self.anchor_rect.borrow() {
+            return Ok(rect);
+        }
+
+        let rect = match self.anchor {
+            Some(ref anchor) => anchor.get_bounding_rectangle()?,
+            None => Rect::default()
+        };
+        *self.anchor_rect.borrow_mut() = Some(rect);
+
+        Ok(rect)
+    }
+}
+
+impl Debug for SpatialFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpatialFilter").field("relation", &self.relation).field("anchor_rect", &self.anchor_rect.borrow()).finish()
+    }
+}
+
+impl MatcherFilter for SpatialFilter {
+    fn judge(&self, element: &UIElement) -> Result<bool> {
+        // the anchor is excluded by its identity, since other elements may have the same rect, such as a pane filled by its only child.
+        if let SpatialRelation::Near(_) = self.relation && self.anchor.as_ref() == Some(element) {
+            return Ok(false);
+        }
+
+        let anchor = self.get_anchor_rect()?;
+        let rect = element.get_bounding_rectangle()?;
+
+        Ok(self.relation.is_satisfied(&rect, &anchor))
+    }
+}
+
 #[cfg(feature = "process")]
 #[derive(Debug)]
 pub struct ProcessIdFilter {
@@ -188,47 +363,13 @@ impl ProcessIdFilter {
     }
 
     fn build_sub_ids(&self) -> Result<()> {
-        let procs = self.get_sub_processes()?;
-
-        let mut sub_ids = vec![self.pid];
-        let mut found = true;
-        while found {
-            found = false;
-            for (pid, ppid) in procs.iter() {
-                if sub_ids.contains(ppid) && !sub_ids.contains(pid) {
-                    sub_ids.push(*pid);
-                    found = true;
-                }
-            }
-        }
+        let sub_ids = ProcessSnapshot::new()?.with_sub_processes(&[self.pid]);
 
         let mut ids = self.progresses.borrow_mut();
         *ids = Some(sub_ids);
 
         Ok(())
     }
-
-    fn get_sub_processes(&self) -> Result<Vec<(u32, u32)>> {
-        let mut ids = Vec::new();
-
-        unsafe  {
-            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)?;
-
-            let mut proc_entry = PROCESSENTRY32::default();
-            proc_entry.dwSize = std::mem::size_of::<PROCESSENTRY32>() as _;
-
-            let mut found = Process32First(snapshot, &mut proc_entry);
-            while found.is_ok() {
-                ids.push((proc_entry.th32ProcessID, proc_entry.th32ParentProcessID));
-
-                found = Process32Next(snapshot, &mut proc_entry);
-            }
-
-            windows::Win32::Foundation::CloseHandle(snapshot)?;
-        }
-
-        Ok(ids)
-    }
 }
 
 #[cfg(feature = "process")]
@@ -243,4 +384,56 @@ impl MatcherFilter for ProcessIdFilter {
             Ok(false)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Rect;
+    use crate::UIAutomation;
+
+    use super::LabeledFilter;
+    use super::MatcherFilter;
+    use super::SpatialFilter;
+    use super::SpatialRelation;
+
+    #[test]
+    fn test_normalize_label() {
+        assert_eq!(LabeledFilter::normalize(" User name: "), "user name");
+        assert_eq!(LabeledFilter::normalize("密码："), "密码");
+        assert_eq!(LabeledFilter::normalize("File name :"), "file name");
+    }
+
+    #[test]
+    fn test_spatial_relation() {
+        let label = Rect::new(10, 10, 80, 30);
+        let edit = Rect::new(90, 8, 300, 32);
+        let button = Rect::new(10, 50, 100, 75);
+        let window = Rect::new(0, 0, 400, 300);
+
+        assert!(SpatialRelation::RightOf.is_satisfied(&edit, &label));
+        assert!(!SpatialRelation::RightOf.is_satisfied(&button, &label));
+        assert!(SpatialRelation::RightOf.is_satisfied(&Rect::new(80, 10, 100, 30), &label));
+        assert!(!SpatialRelation::RightOf.is_satisfied(&Rect::new(79, 10, 100, 30), &label));
+        assert!(!SpatialRelation::RightOf.is_satisfied(&Rect::new(80, 30, 100, 40), &label));
+        assert!(SpatialRelation::LeftOf.is_satisfied(&label, &edit));
+        assert!(SpatialRelation::Below.is_satisfied(&button, &label));
+        assert!(!SpatialRelation::Below.is_satisfied(&edit, &label));
+        assert!(SpatialRelation::Above.is_satisfied(&label, &button));
+        assert!(SpatialRelation::Near(10).is_satisfied(&edit, &label));
+        assert!(!SpatialRelation::Near(10).is_satisfied(&button, &label));
+        assert!(SpatialRelation::Near(10).is_satisfied(&label, &label));
+        assert!(SpatialRelation::Inside.is_satisfied(&button, &window));
+        assert!(!SpatialRelation::Inside.is_satisfied(&window, &button));
+    }
+
+    #[test]
+    fn test_near_anchor() {
+        let automation = UIAutomation::new().unwrap();
+        let root = automation.get_root_element().unwrap();
+
+        // the anchor element is excluded, but an element with the same rect is not.
+        assert!(!SpatialFilter::new(SpatialRelation::Near(0), &root).judge(&root).unwrap());
+        let rect = root.get_bounding_rectangle().unwrap();
+        assert!(SpatialFilter::from_rect(SpatialRelation::Near(0), rect).judge(&root).unwrap());
+    }
+}
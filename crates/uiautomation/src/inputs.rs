@@ -14,6 +14,7 @@ use windows::Win32::UI::WindowsAndMessaging::SM_CXSCREEN;
 use windows::Win32::UI::WindowsAndMessaging::SM_CYSCREEN;
 use windows::Win32::UI::WindowsAndMessaging::SetCursorPos;
 
+use crate::cancellations::CancellationToken;
 use crate::log_error;
 
 use super::errors::{ERR_FORMAT, ERR_INVALID_ARG};
@@ -539,6 +540,7 @@ pub struct Keyboard {
     interval: u64,
     holdkeys: Vec<VIRTUAL_KEY>,
     ignore_parse_err: bool,
+    cancellation: Option<CancellationToken>,
 }
 
 impl Keyboard {
@@ -548,6 +550,7 @@ impl Keyboard {
             interval: 50,
             holdkeys: Vec::new(),
             ignore_parse_err: false,
+            cancellation: None,
         }
     }
 
@@ -572,6 +575,14 @@ impl Keyboard {
         self
     }
 
+    /// Sets a token to cancel typing. The remaining keys are skipped and a cancelled error occurs as soon as the token is cancelled.
+    /// 
+    /// The keys pressed by the cancelled typing and the held keys are released when it is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Simulates typing `keys` on keyboard.
     /// 
     /// `{}` is used for some special keys. For example: `{ctrl}{alt}{delete}`, `{shift}{home}`.
@@ -647,6 +658,8 @@ impl Keyboard {
     }
     
     /// Stop holding keys on keyboard. 
+    /// 
+    /// The keys are released even if the cancellation token is cancelled.
     pub fn end_hold_keys(&mut self) -> Result<()> {
         if self.holdkeys.is_empty() {
             Ok(())
@@ -655,34 +668,97 @@ impl Keyboard {
             for holdkey in self.holdkeys.iter().rev() {
                 holdkey_inputs.push(Input::create_virtual_key(*holdkey, KEYEVENTF_KEYUP));
             }
+
+            send_input(&holdkey_inputs)?;
             self.holdkeys.clear();
 
-            // send_input(&holdkey_inputs.as_slice())
-            self.send_keyboard(&holdkey_inputs)
+            Ok(())
         }
     }
 
     fn send_keyboard(&self, input_keys: &[INPUT]) -> Result<()> {
-        // let input_keys = input.create_inputs()?;
+        if let Err(e) = self.check_cancelled() {
+            self.release_keys(&[]);
+            return Err(e);
+        }
+
         if self.interval == 0 {
             send_input(input_keys)
         } else {
-            for input_key in input_keys {
+            for (index, input_key) in input_keys.iter().enumerate() {
+                if index > 0 && let Err(e) = self.check_cancelled() {
+                    self.release_keys(&input_keys[..index]);
+                    return Err(e);
+                }
+
                 let input_key_slice: [INPUT; 1] = [input_key.clone()];
                 send_input(&input_key_slice)?;
 
-                self.wait();
+                if let Err(e) = self.wait() {
+                    self.release_keys(&input_keys[..=index]);
+                    return Err(e);
+                }
             }
 
             Ok(())
         }
     }
 
+    fn check_cancelled(&self) -> Result<()> {
+        match self.cancellation {
+            Some(ref token) => token.check(),
+            None => Ok(())
+        }
+    }
+
+    /// Releases the keys which are still down after the `sent` inputs, and the held keys, when typing is cancelled.
+    /// 
+    /// The held keys are kept in `holdkeys`, so `end_hold_keys()` can still be called.
+    fn release_keys(&self, sent: &[INPUT]) {
+        let mut release_inputs = Self::pending_key_ups(sent);
+        for holdkey in self.holdkeys.iter().rev() {
+            release_inputs.push(Input::create_virtual_key(*holdkey, KEYEVENTF_KEYUP));
+        }
 
-    fn wait(&self) {
+        if !release_inputs.is_empty() && let Err(e) = send_input(&release_inputs) {
+            log_error!("Error releasing keys: {}", e);
+        }
+    }
+
+    /// Creates the key-up inputs of the keys which are down after the `sent` inputs, in reverse order.
+    fn pending_key_ups(sent: &[INPUT]) -> Vec<INPUT> {
+        let mut down_keys: Vec<INPUT> = Vec::new();
+        for input in sent {
+            if input.r#type != INPUT_KEYBOARD {
+                continue;
+            }
+
+            let ki = unsafe { input.Anonymous.ki };
+            down_keys.retain(|down| {
+                let down_ki = unsafe { down.Anonymous.ki };
+                down_ki.wVk != ki.wVk || down_ki.wScan != ki.wScan
+            });
+            if !ki.dwFlags.contains(KEYEVENTF_KEYUP) {
+                down_keys.push(*input);
+            }
+        }
+
+        down_keys.into_iter().rev().map(|mut input| {
+            unsafe { input.Anonymous.ki.dwFlags |= KEYEVENTF_KEYUP };
+            input
+        }).collect()
+    }
+
+    fn wait(&self) -> Result<()> {
         if self.interval > 0 {
-            sleep(Duration::from_millis(self.interval));
+            if let Some(ref token) = self.cancellation {
+                token.sleep(Duration::from_millis(self.interval))?;
+            } else {
+                sleep(Duration::from_millis(self.interval));
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -740,7 +816,8 @@ pub struct Mouse {
     interval: u64,
     move_time: u64,
     auto_move: bool,
-    holdkeys: Vec<VIRTUAL_KEY>
+    holdkeys: Vec<VIRTUAL_KEY>,
+    cancellation: Option<CancellationToken>
 }
 
 impl Default for Mouse {
@@ -749,7 +826,8 @@ impl Default for Mouse {
             interval: 100, 
             move_time: 500,
             auto_move: true,
-            holdkeys: Vec::new()
+            holdkeys: Vec::new(),
+            cancellation: None
         }
     }
 }
@@ -780,6 +858,12 @@ impl Mouse {
         self
     }
 
+    /// Sets a token to cancel mouse moving. A cancelled error occurs as soon as the token is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Sets the holdkeys when mouse clicks.
     /// 
     /// The holdkeys is quoted by `{}`. For example: `{Shift}`, `{Ctrl}{Alt}`.
@@ -826,6 +910,10 @@ impl Mouse {
     /// mouse.move_to(&Point::new(1000,400)).unwrap();
     /// ```
     pub fn move_to(&self, target: &Point) -> Result<()> {
+        if let Some(ref token) = self.cancellation {
+            token.check()?;
+        }
+
         if self.move_time > 0 {
             let source = Self::get_cursor_pos()?;
             let delta_x = target.get_x() - source.get_x();
@@ -843,7 +931,11 @@ impl Mouse {
                         source.get_y() + step_y * i
                     );
                     Self::mouse_move_event(&pos)?;
-                    sleep(interval);
+                    if let Some(ref token) = self.cancellation {
+                        token.sleep(interval)?;
+                    } else {
+                        sleep(interval);
+                    }
                 }
             }
         }
@@ -1157,13 +1249,17 @@ mod tests {
 
     use windows::Win32::UI::Input::KeyboardAndMouse::*;
 
+    use crate::cancellations::CancellationToken;
+    use crate::errors::ERR_CANCELLED;
     use crate::inputs::get_screen_size;
     use crate::inputs::init_virtual_keys;
     use crate::inputs::Keyboard;
+    use crate::inputs::Mouse;
     use crate::inputs::Input;
     use crate::inputs::InputItem;
     use crate::inputs::Parser;
     use crate::inputs::VIRTUAL_KEYS;
+    use crate::types::Point;
 
     #[test]
     fn test_virtual_keys() {
@@ -1374,4 +1470,45 @@ mod tests {
         assert!(w > 0 && h > 0);
         println!("Screen size: {}x{}", w, h);
     }
+
+    #[test]
+    fn test_pending_key_ups() {
+        let inputs = Parser::new(false).parse_input("{ctrl}(AB)").unwrap();
+        let keys = inputs[0].create_inputs().unwrap();
+
+        // cancelled after `Ctrl` and `A` are pressed down.
+        let ups = Keyboard::pending_key_ups(&keys[..2]);
+        assert_eq!(ups.len(), 2);
+        let first = unsafe { ups[0].Anonymous.ki };
+        let second = unsafe { ups[1].Anonymous.ki };
+        assert!(first.dwFlags.contains(KEYEVENTF_KEYUP) && second.dwFlags.contains(KEYEVENTF_KEYUP));
+        assert_eq!(second.wVk, VK_CONTROL);
+
+        assert!(Keyboard::pending_key_ups(&keys).is_empty());
+    }
+
+    #[test]
+    fn test_cancel_keyboard() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let kb = Keyboard::new().cancellation(token);
+        assert_eq!(kb.send_keys("{ctrl}(AB)").unwrap_err().code(), ERR_CANCELLED);
+
+        let mut kb = kb;
+        assert_eq!(kb.begin_hold_keys("{shift}").unwrap_err().code(), ERR_CANCELLED);
+        assert!(kb.end_hold_keys().is_ok());
+    }
+
+    #[test]
+    fn test_cancel_mouse() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let mouse = Mouse::new().move_time(500).cancellation(token);
+        let pos = Mouse::get_cursor_pos().unwrap();
+        let target = Point::new(if pos.get_x() > 200 { pos.get_x() - 200 } else { pos.get_x() + 200 }, pos.get_y());
+        assert_eq!(mouse.move_to(&target).unwrap_err().code(), ERR_CANCELLED);
+        assert!(Mouse::get_cursor_pos().unwrap() == pos);
+    }
 }
@@ -0,0 +1,300 @@
+use std::fmt::Display;
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::errors::ERR_FORMAT;
+use super::errors::Error;
+use super::errors::Result;
+
+/// A minimal JSON value, used to read and write the JSON Lines files.
+///
+/// The numbers are kept as their text, so the 64-bit integers are not truncated.
+/// JSON has no NaN or infinity, so they are written as the strings `"NaN"`, `"inf"` and `"-inf"`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>)
+}
+
+impl Json {
+    /// Creates a number from any integer or float value. The non-finite values are created as strings.
+    pub fn number<T: Display>(value: T) -> Self {
+        let text = value.to_string();
+        if text.parse::<f64>().map(|v| v.is_finite()).unwrap_or(false) {
+            Self::Number(text)
+        } else {
+            Self::String(text)
+        }
+    }
+
+    /// Creates a string.
+    pub fn string<S: Into<String>>(value: S) -> Self {
+        Self::String(value.into())
+    }
+
+    /// Creates an object from the `(key, value)` pairs.
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Self {
+        Self::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    /// Parses a JSON text.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut chars = text.chars().peekable();
+        let value = Self::parse_value(&mut chars)?;
+        Self::skip_whitespace(&mut chars);
+        if chars.peek().is_some() {
+            return Err(Error::new(ERR_FORMAT, "unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Retrieves the field `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    /// Retrieves the field `key` of an object, or returns a format error.
+    pub fn field(&self, key: &str) -> Result<&Json> {
+        self.get(key).ok_or_else(|| Error::new(ERR_FORMAT, &format!("missing field: {}", key)))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn as_str(&self) -> Result<&str> {
+        match self {
+            Self::String(s) => Ok(s),
+            _ => Err(Error::new(ERR_FORMAT, "expect a string"))
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool> {
+        match self {
+            Self::Bool(b) => Ok(*b),
+            _ => Err(Error::new(ERR_FORMAT, "expect a bool"))
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[Json]> {
+        match self {
+            Self::Array(items) => Ok(items),
+            _ => Err(Error::new(ERR_FORMAT, "expect an array"))
+        }
+    }
+
+    /// Parses the number as `T`, such as `i32`, `u64` or `f64`.
+    pub fn as_number<T: std::str::FromStr>(&self) -> Result<T> {
+        match self {
+            Self::Number(n) => n.parse().map_err(|_| Error::new(ERR_FORMAT, &format!("invalid number: {}", n))),
+            Self::String(s) if matches!(s.as_str(), "NaN" | "inf" | "-inf") => s.parse().map_err(|_| Error::new(ERR_FORMAT, &format!("invalid number: {}", s))),
+            _ => Err(Error::new(ERR_FORMAT, "expect a number"))
+        }
+    }
+
+    fn skip_whitespace(chars: &mut Peekable<Chars>) {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect_word(chars: &mut Peekable<Chars>, word: &str) -> Result<()> {
+        for expected in word.chars() {
+            if chars.next() != Some(expected) {
+                return Err(Error::new(ERR_FORMAT, &format!("expect `{}`", word)));
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_value(chars: &mut Peekable<Chars>) -> Result<Self> {
+        Self::skip_whitespace(chars);
+        match chars.peek() {
+            Some('n') => Self::expect_word(chars, "null").map(|_| Self::Null),
+            Some('t') => Self::expect_word(chars, "true").map(|_| Self::Bool(true)),
+            Some('f') => Self::expect_word(chars, "false").map(|_| Self::Bool(false)),
+            Some('"') => Self::parse_string(chars).map(Self::String),
+            Some('[') => {
+                chars.next();
+                let mut items = Vec::new();
+                Self::skip_whitespace(chars);
+                if chars.next_if_eq(&']').is_some() {
+                    return Ok(Self::Array(items));
+                }
+                loop {
+                    items.push(Self::parse_value(chars)?);
+                    Self::skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Self::Array(items)),
+                        _ => return Err(Error::new(ERR_FORMAT, "expect `,` or `]`"))
+                    }
+                }
+            },
+            Some('{') => {
+                chars.next();
+                let mut fields = Vec::new();
+                Self::skip_whitespace(chars);
+                if chars.next_if_eq(&'}').is_some() {
+                    return Ok(Self::Object(fields));
+                }
+                loop {
+                    Self::skip_whitespace(chars);
+                    let key = Self::parse_string(chars)?;
+                    Self::skip_whitespace(chars);
+                    if chars.next() != Some(':') {
+                        return Err(Error::new(ERR_FORMAT, "expect `:`"));
+                    }
+                    fields.push((key, Self::parse_value(chars)?));
+                    Self::skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Self::Object(fields)),
+                        _ => return Err(Error::new(ERR_FORMAT, "expect `,` or `}`"))
+                    }
+                }
+            },
+            Some(c) if *c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+                    number.push(c);
+                }
+                if number.parse::<f64>().is_err() {
+                    return Err(Error::new(ERR_FORMAT, &format!("invalid number: {}", number)));
+                }
+                Ok(Self::Number(number))
+            },
+            _ => Err(Error::new(ERR_FORMAT, "unexpected character"))
+        }
+    }
+
+    fn parse_string(chars: &mut Peekable<Chars>) -> Result<String> {
+        if chars.next() != Some('"') {
+            return Err(Error::new(ERR_FORMAT, "expect a string"));
+        }
+
+        let mut text = String::new();
+        loop {
+            match chars.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => match chars.next() {
+                    Some('"') => text.push('"'),
+                    Some('\\') => text.push('\\'),
+                    Some('/') => text.push('/'),
+                    Some('b') => text.push('\u{08}'),
+                    Some('f') => text.push('\u{0C}'),
+                    Some('n') => text.push('\n'),
+                    Some('r') => text.push('\r'),
+                    Some('t') => text.push('\t'),
+                    Some('u') => {
+                        let high = Self::parse_hex(chars)?;
+                        let code = if (0xD800..0xDC00).contains(&high) {
+                            Self::expect_word(chars, "\\u")?;
+                            let low = Self::parse_hex(chars)?;
+                            0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+                        } else {
+                            high
+                        };
+                        text.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    },
+                    _ => return Err(Error::new(ERR_FORMAT, "invalid escape"))
+                },
+                Some(c) => text.push(c),
+                None => return Err(Error::new(ERR_FORMAT, "unterminated string"))
+            }
+        }
+    }
+
+    fn parse_hex(chars: &mut Peekable<Chars>) -> Result<u32> {
+        let hex: String = chars.by_ref().take(4).collect();
+        u32::from_str_radix(&hex, 16).map_err(|_| Error::new(ERR_FORMAT, "invalid unicode escape"))
+    }
+
+    fn write_string(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+        f.write_char('"')?;
+        for c in text.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+impl Display for Json {
+    /// Writes the value as compact JSON in one line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Number(n) => f.write_str(n),
+            Self::String(s) => Self::write_string(f, s),
+            Self::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            },
+            Self::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    Self::write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+
+    #[test]
+    fn test_json() {
+        let value = Json::object([
+            ("name", Json::string("Save \"As\"\n\u{1}")),
+            ("id", Json::number(u64::MAX)),
+            ("ratio", Json::number(0.5)),
+            ("nan", Json::number(f64::NAN)),
+            ("inf", Json::number(f32::NEG_INFINITY)),
+            ("items", Json::Array(vec![Json::Bool(true), Json::Null]))
+        ]);
+        let text = value.to_string();
+        assert_eq!(text, r#"{"name":"Save \"As\"\n\u0001","id":18446744073709551615,"ratio":0.5,"nan":"NaN","inf":"-inf","items":[true,null]}"#);
+        assert_eq!(Json::parse(&text).unwrap(), value);
+        assert_eq!(value.field("id").unwrap().as_number::<u64>().unwrap(), u64::MAX);
+        assert!(value.field("nan").unwrap().as_number::<f64>().unwrap().is_nan());
+        assert_eq!(value.field("inf").unwrap().as_number::<f32>().unwrap(), f32::NEG_INFINITY);
+        assert!(value.field("nan").unwrap().as_number::<i32>().is_err());
+        assert!(Json::string("1").as_number::<f64>().is_err());
+
+        let value = Json::parse(r#" { "a" : [ 1 , -2.5e3 ] , "b" : "中😀" } "#).unwrap();
+        assert_eq!(value.field("a").unwrap().as_array().unwrap()[1].as_number::<f64>().unwrap(), -2500.0);
+        assert_eq!(value.field("b").unwrap().as_str().unwrap(), "中😀");
+
+        assert!(Json::parse("{\"a\":1").is_err());
+        assert!(Json::parse("[1,]").is_err());
+        assert!(Json::parse("1 2").is_err());
+    }
+}
@@ -3,6 +3,9 @@ pub mod types;
 pub mod variants;
 pub mod core;
 pub mod filters;
+pub mod texts;
+pub mod locales;
+pub mod cancellations;
 pub(crate) mod logs;
 
 #[cfg(feature = "process")]
@@ -19,8 +22,12 @@ pub mod actions;
 pub mod controls;
 #[cfg(feature = "event")]
 pub mod events;
+#[cfg(feature = "event")]
+pub(crate) mod jsons;
 #[cfg(feature = "clipboard")]
 pub mod clipboards;
+#[cfg(feature = "async")]
+pub mod asyncs;
 
 pub use self::errors::Error;
 pub use self::errors::Result;
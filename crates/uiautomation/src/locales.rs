@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use windows::Win32::Globalization::GetUserPreferredUILanguages;
+use windows::Win32::Globalization::MUI_LANGUAGE_NAME;
+use windows::core::PWSTR;
+
+use super::core::UIElement;
+use super::errors::ERR_FORMAT;
+use super::errors::Error;
+use super::errors::Result;
+use super::filters::MatcherFilter;
+
+static DEFAULT_ALIASES: RwLock<Option<Arc<LocaleAliases>>> = RwLock::new(None);
+
+/// Retrieves the preferred UI languages of the current user, such as `["zh-CN", "en-US"]`.
+pub fn get_ui_languages() -> Result<Vec<String>> {
+    let mut count = 0u32;
+    let mut len = 0u32;
+    unsafe {
+        GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, None, &mut len)?;
+    }
+
+    let mut buffer = vec![0u16; len as usize];
+    unsafe {
+        GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, Some(PWSTR(buffer.as_mut_ptr())), &mut len)?;
+    }
+
+    let languages = buffer.split(|c| *c == 0)
+        .filter(|s| !s.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect();
+    Ok(languages)
+}
+
+/// Retrieves the current UI language of the current user, such as `zh-CN`.
+pub fn get_ui_language() -> Result<String> {
+    get_ui_languages()?.into_iter().next().ok_or_else(|| Error::new(ERR_FORMAT, "no ui language"))
+}
+
+/// A table mapping logical keys to the localized names per UI language, used to write locale independent locators.
+///
+/// The table can be loaded from a text file, where a `[key]` line starts a key and each `language = name` line adds a name.
+/// Multiple names of one language are separated by `|`, and the lines starting with `#` are comments:
+///
+/// ```text
+/// [start]
+/// zh-CN = 开始
+/// en = Start
+///
+/// [settings]
+/// zh-CN = 设置
+/// en = Settings
+/// de = Einstellungen
+/// ```
+///
+/// A name of the language `en` is used for `en-US`, `en-GB` and so on.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::UIAutomation;
+/// use uiautomation::locales::LocaleAliases;
+///
+/// let aliases = LocaleAliases::from_file("aliases.txt").unwrap();
+/// aliases.set_default();
+///
+/// let automation = UIAutomation::new().unwrap();
+/// let start = automation.create_matcher().name_key("start").find_first();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocaleAliases {
+    language: Option<String>,
+    aliases: HashMap<String, HashMap<String, Vec<String>>>
+}
+
+impl LocaleAliases {
+    /// Creates an empty table for the current UI language.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a table from `text`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut aliases = Self::new();
+        let mut key: Option<String> = None;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                key = Some(section.trim().to_string());
+            } else if let Some((language, names)) = line.split_once('=') {
+                let Some(ref key) = key else {
+                    return Err(Error::new(ERR_FORMAT, &format!("line {}: alias without key", index + 1)));
+                };
+                for name in names.split('|') {
+                    aliases.add(key, language.trim(), name.trim());
+                }
+            } else {
+                return Err(Error::new(ERR_FORMAT, &format!("line {}: invalid alias", index + 1)));
+            }
+        }
+
+        Ok(aliases)
+    }
+
+    /// Loads a table from the file at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| Error::from(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    /// Adds the `name` of `key` for `language`.
+    pub fn add(&mut self, key: &str, language: &str, name: &str) {
+        let names = self.aliases.entry(key.to_string()).or_default().entry(language.to_lowercase()).or_default();
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    /// Sets the active language, instead of the current UI language.
+    pub fn with_language<S: Into<String>>(mut self, language: S) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Retrieves the active language, which is the current UI language by default.
+    pub fn get_language(&self) -> Result<String> {
+        match self.language {
+            Some(ref language) => Ok(language.clone()),
+            None => get_ui_language()
+        }
+    }
+
+    /// Retrieves the names of `key` for the active language.
+    ///
+    /// The names of the exact language are preferred, then the names of the same primary language.
+    /// All the names of `key` are returned when there is no name for the active language, and `key` itself is returned when `key` is unknown.
+    pub fn get_names(&self, key: &str) -> Vec<String> {
+        let language = self.get_language().unwrap_or_default();
+        self.get_names_for(key, &language)
+    }
+
+    /// Retrieves the names of `key` for `language`, in the same way as `get_names()`.
+    pub fn get_names_for(&self, key: &str, language: &str) -> Vec<String> {
+        let Some(languages) = self.aliases.get(key) else {
+            return vec![key.to_string()];
+        };
+
+        let language = language.to_lowercase();
+        if let Some(names) = languages.get(&language) {
+            return names.clone();
+        }
+
+        let primary = language.split('-').next().unwrap_or_default();
+        let mut names: Vec<String> = Vec::new();
+        for (lang, lang_names) in languages.iter() {
+            if lang.split('-').next() == Some(primary) {
+                names.extend(lang_names.iter().cloned());
+            }
+        }
+
+        if names.is_empty() {
+            languages.values().flatten().cloned().collect()
+        } else {
+            names
+        }
+    }
+
+    /// Creates a filter matching any name of `key` for the active language (ignore casesensitive).
+    pub fn filter(&self, key: &str) -> AliasNameFilter {
+        AliasNameFilter {
+            names: self.get_names(key)
+        }
+    }
+
+    /// Sets this table as the default table used by `UIMatcher::name_key()`.
+    pub fn set_default(self) {
+        if let Ok(mut aliases) = DEFAULT_ALIASES.write() {
+            *aliases = Some(Arc::new(self));
+        }
+    }
+
+    /// Retrieves the default table, which is empty if it is not set.
+    pub fn get_default() -> Arc<LocaleAliases> {
+        DEFAULT_ALIASES.read().ok().and_then(|aliases| aliases.clone()).unwrap_or_default()
+    }
+}
+
+/// Filters the elements whose name is any of the aliases (ignore casesensitive).
+#[derive(Debug, Clone, Default)]
+pub struct AliasNameFilter {
+    pub names: Vec<String>
+}
+
+impl MatcherFilter for AliasNameFilter {
+    fn judge(&self, element: &UIElement) -> Result<bool> {
+        let name = element.get_name()?;
+        Ok(self.names.iter().any(|n| n.eq_ignore_ascii_case(&name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocaleAliases;
+    use super::get_ui_language;
+
+    #[test]
+    fn test_parse_aliases() {
+        let aliases = LocaleAliases::parse("
+            # sample aliases
+            [start]
+            zh-CN = 开始
+            en = Start
+
+            [settings]
+            zh-CN = 设置
+            en-US = Settings | Setting
+            de = Einstellungen
+        ").unwrap();
+
+        assert_eq!(aliases.get_names_for("start", "zh-CN"), vec!["开始"]);
+        assert_eq!(aliases.get_names_for("start", "en-GB"), vec!["Start"]);
+        assert_eq!(aliases.get_names_for("settings", "EN-us"), vec!["Settings", "Setting"]);
+        assert_eq!(aliases.get_names_for("settings", "de-DE"), vec!["Einstellungen"]);
+        assert_eq!(aliases.get_names_for("start", "fr-FR").len(), 2);
+        assert_eq!(aliases.get_names_for("unknown", "en-US"), vec!["unknown"]);
+
+        let aliases = aliases.with_language("zh-CN");
+        assert_eq!(aliases.get_names("settings"), vec!["设置"]);
+
+        assert!(LocaleAliases::parse("zh-CN = 开始").is_err());
+        assert!(LocaleAliases::parse("[start]\nStart").is_err());
+    }
+
+    #[test]
+    fn test_ui_language() {
+        let language = get_ui_language().unwrap();
+        println!("UI language: {}", language);
+        assert!(!language.is_empty());
+    }
+}
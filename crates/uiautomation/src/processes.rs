@@ -6,9 +6,18 @@ use windows::Win32::Foundation::HANDLE;
 use windows::Win32::Foundation::WAIT_FAILED;
 use windows::Win32::Foundation::WAIT_OBJECT_0;
 use windows::Win32::Foundation::WAIT_TIMEOUT;
+use windows::Win32::System::Diagnostics::ToolHelp::CreateToolhelp32Snapshot;
+use windows::Win32::System::Diagnostics::ToolHelp::PROCESSENTRY32W;
+use windows::Win32::System::Diagnostics::ToolHelp::Process32FirstW;
+use windows::Win32::System::Diagnostics::ToolHelp::Process32NextW;
+use windows::Win32::System::Diagnostics::ToolHelp::TH32CS_SNAPPROCESS;
 use windows::Win32::System::Threading::CreateProcessW;
 use windows::Win32::System::Threading::GetExitCodeProcess;
 use windows::Win32::System::Threading::INFINITE;
+use windows::Win32::System::Threading::OpenProcess;
+use windows::Win32::System::Threading::PROCESS_NAME_WIN32;
+use windows::Win32::System::Threading::PROCESS_QUERY_LIMITED_INFORMATION;
+use windows::Win32::System::Threading::QueryFullProcessImageNameW;
 use windows::Win32::System::Threading::PROCESS_CREATION_FLAGS;
 use windows::Win32::System::Threading::PROCESS_INFORMATION;
 use windows::Win32::System::Threading::STARTUPINFOW;
@@ -22,6 +31,7 @@ use super::Result;
 use crate::errors::ERR_ALREADY_RUNNING;
 use crate::types::Handle;
 use super::errors::ERR_NONE;
+use super::errors::ERR_NOTFOUND;
 use super::errors::ERR_TIMEOUT;
 
 /// Windows process wrapper.
@@ -217,6 +227,111 @@ impl Process {
     }
 }
 
+/// The basic information of a running process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    /// The process id.
+    pub id: u32,
+    /// The full path of the executable file, which is empty if the process can not be queried.
+    pub path: String,
+    /// The executable file name, such as `notepad.exe`.
+    pub name: String
+}
+
+impl ProcessInfo {
+    /// Retrieves the information of the process `pid`.
+    pub fn from_id(pid: u32) -> Result<Self> {
+        let path = Self::query_image_path(pid).unwrap_or_default();
+        let name = match path.rsplit(['\\', '/']).next() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => ProcessSnapshot::new()?.entries.into_iter()
+                .find(|(id, _, _)| *id == pid)
+                .map(|(_, _, name)| name)
+                .ok_or_else(|| Error::new(ERR_NOTFOUND, "can not find process"))?
+        };
+
+        Ok(Self {
+            id: pid,
+            path,
+            name
+        })
+    }
+
+    fn query_image_path(pid: u32) -> Result<String> {
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)?;
+            let result = QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut len);
+            let _ = CloseHandle(handle);
+            result?;
+        }
+
+        Ok(String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+}
+
+/// A snapshot of the running processes, which is used to look up processes by name and collect sub processes.
+pub(crate) struct ProcessSnapshot {
+    entries: Vec<(u32, u32, String)>
+}
+
+impl ProcessSnapshot {
+    /// Takes a snapshot of the running processes.
+    pub(crate) fn new() -> Result<Self> {
+        let mut entries = Vec::new();
+
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)?;
+
+            let mut proc_entry = PROCESSENTRY32W {
+                dwSize: mem::size_of::<PROCESSENTRY32W>() as _,
+                ..Default::default()
+            };
+
+            let mut found = Process32FirstW(snapshot, &mut proc_entry);
+            while found.is_ok() {
+                let len = proc_entry.szExeFile.iter().position(|c| *c == 0).unwrap_or(proc_entry.szExeFile.len());
+                let name = String::from_utf16_lossy(&proc_entry.szExeFile[..len]);
+                entries.push((proc_entry.th32ProcessID, proc_entry.th32ParentProcessID, name));
+
+                found = Process32NextW(snapshot, &mut proc_entry);
+            }
+
+            CloseHandle(snapshot)?;
+        }
+
+        Ok(Self {
+            entries
+        })
+    }
+
+    /// Finds the ids of the processes whose executable file name is `name` (ignore casesensitive).
+    pub(crate) fn find_by_name(&self, name: &str) -> Vec<u32> {
+        self.entries.iter()
+            .filter(|(_, _, exe)| exe.eq_ignore_ascii_case(name))
+            .map(|(pid, _, _)| *pid)
+            .collect()
+    }
+
+    /// Appends the ids of all the sub processes of `pids`. The `pids` are kept at the beginning in order.
+    pub(crate) fn with_sub_processes(&self, pids: &[u32]) -> Vec<u32> {
+        let mut ids = pids.to_vec();
+        let mut found = true;
+        while found {
+            found = false;
+            for (pid, ppid, _) in self.entries.iter() {
+                if ids.contains(ppid) && !ids.contains(pid) {
+                    ids.push(*pid);
+                    found = true;
+                }
+            }
+        }
+
+        ids
+    }
+}
+
 macro_rules! close_handle {
     ($handle: expr) => {
         if !$handle.is_invalid() {
@@ -299,4 +414,47 @@ mod tests {
             println!("Notepad not found.");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn find_in_process() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        use windows::Win32::UI::WindowsAndMessaging::*;
+        use windows::core::w;
+
+        const TITLE: &str = "uiautomation find in process test";
+
+        let (close_sender, close_receiver) = mpsc::channel::<()>();
+        let window_thread = std::thread::spawn(move || unsafe {
+            let hwnd = CreateWindowExW(WINDOW_EX_STYLE::default(), w!("STATIC"), w!("uiautomation find in process test"), WS_OVERLAPPEDWINDOW | WS_VISIBLE, 0, 0, 300, 200, None, None, None, None).unwrap();
+            let mut msg = MSG::default();
+            while close_receiver.try_recv().is_err() {
+                while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            DestroyWindow(hwnd).unwrap();
+        });
+
+        let automation = UIAutomation::new().unwrap();
+        let window = automation.create_matcher()
+            .in_process(std::process::id())
+            .name(TITLE)
+            .depth(2)
+            .timeout(5000)
+            .find_first()
+            .unwrap();
+        assert_eq!(window.get_process_id().unwrap(), std::process::id());
+
+        let exe = std::env::current_exe().unwrap();
+        let exe = exe.file_name().unwrap().to_string_lossy();
+        assert!(automation.create_matcher().in_process_name(exe).name(TITLE).depth(2).timeout(0).exists());
+        assert!(!automation.create_matcher().in_process(u32::MAX).name(TITLE).depth(2).timeout(0).exists());
+
+        close_sender.send(()).unwrap();
+        window_thread.join().unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+use windows::Win32::Globalization::NormalizationKC;
+use windows::Win32::Globalization::NormalizeString;
+
+/// Defines a step of the name normalization pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeStep {
+    /// Applies Unicode NFKC normalization, which folds full-width and compatibility characters, such as `Ｏｋ` to `Ok`.
+    Nfkc,
+    /// Strips the keyboard shortcut suffix after a tab, such as `Save\tCtrl+S` to `Save`.
+    StripShortcut,
+    /// Strips the accelerator ampersands, such as `&Open` to `Open` and `文件(&F)` to `文件`.
+    StripAccelerator,
+    /// Strips the trailing ellipsis, such as `Save As...` to `Save As`.
+    StripEllipsis,
+    /// Replaces all whitespace runs, including non-breaking spaces, with a single space and trims the text.
+    CollapseWhitespace,
+    /// Converts the text to lower case.
+    Lowercase
+}
+
+/// A pipeline which normalizes UI names before comparing them.
+///
+/// # Examples
+///
+/// ```no_run
+/// use uiautomation::texts::NameNormalizer;
+///
+/// let normalizer = NameNormalizer::default();
+/// assert_eq!(normalizer.normalize("Save &As...\tCtrl+Shift+S"), "save as");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameNormalizer {
+    steps: Vec<NormalizeStep>
+}
+
+impl NameNormalizer {
+    /// Creates an empty pipeline, which keeps the texts unchanged.
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new()
+        }
+    }
+
+    /// Appends a step to the pipeline.
+    pub fn step(mut self, step: NormalizeStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Retrieves the steps of the pipeline.
+    pub fn get_steps(&self) -> &[NormalizeStep] {
+        &self.steps
+    }
+
+    /// Normalizes `text` by all the steps in order.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for step in self.steps.iter() {
+            text = match step {
+                NormalizeStep::Nfkc => nfkc(&text),
+                NormalizeStep::StripShortcut => strip_shortcut(&text).to_string(),
+                NormalizeStep::StripAccelerator => strip_accelerator(&text),
+                NormalizeStep::StripEllipsis => strip_ellipsis(&text).to_string(),
+                NormalizeStep::CollapseWhitespace => collapse_whitespace(&text),
+                NormalizeStep::Lowercase => text.to_lowercase()
+            };
+        }
+        text
+    }
+}
+
+impl Default for NameNormalizer {
+    /// Creates the full pipeline: NFKC, shortcut, accelerator, ellipsis, whitespace and case.
+    fn default() -> Self {
+        Self {
+            steps: vec![
+                NormalizeStep::Nfkc,
+                NormalizeStep::StripShortcut,
+                NormalizeStep::StripAccelerator,
+                NormalizeStep::StripEllipsis,
+                NormalizeStep::CollapseWhitespace,
+                NormalizeStep::Lowercase
+            ]
+        }
+    }
+}
+
+/// Applies Unicode NFKC normalization to `text`. The text is returned unchanged if it can not be normalized.
+pub fn nfkc(text: &str) -> String {
+    let src: Vec<u16> = text.encode_utf16().collect();
+    if src.is_empty() {
+        return String::new();
+    }
+
+    let mut len = unsafe {
+        NormalizeString(NormalizationKC, &src, None)
+    };
+    // the estimated length may be too small, so retry with the length returned by the failed call.
+    for _ in 0..3 {
+        if len <= 0 {
+            break;
+        }
+
+        let mut dst = vec![0u16; len as usize];
+        let ret = unsafe {
+            NormalizeString(NormalizationKC, &src, Some(&mut dst))
+        };
+        if ret > 0 {
+            return String::from_utf16_lossy(&dst[..ret as usize]);
+        }
+        len = -ret;
+    }
+
+    text.to_string()
+}
+
+/// Strips the keyboard shortcut suffix after the first tab of `text`.
+pub fn strip_shortcut(text: &str) -> &str {
+    match text.find('\t') {
+        Some(pos) => &text[..pos],
+        None => text
+    }
+}
+
+/// Strips the accelerator ampersands of `text`. A double ampersand `&&` is kept as a literal `&`,
+/// and a parenthesized accelerator such as `(&F)` is removed completely.
+pub fn strip_accelerator(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '(' if i + 3 < chars.len() && chars[i + 1] == '&' && chars[i + 2] != '&' && chars[i + 3] == ')' => {
+                i += 4;
+            },
+            '&' if i + 1 < chars.len() && chars[i + 1] == '&' => {
+                result.push('&');
+                i += 2;
+            },
+            '&' => {
+                i += 1;
+            },
+            c => {
+                result.push(c);
+                i += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Strips the trailing ellipsis (`...` or `…`) of `text`.
+pub fn strip_ellipsis(text: &str) -> &str {
+    let trimmed = text.trim_end();
+    if let Some(stripped) = trimmed.strip_suffix("...") {
+        stripped
+    } else if let Some(stripped) = trimmed.strip_suffix('…') {
+        stripped
+    } else {
+        text
+    }
+}
+
+/// Replaces all whitespace runs of `text`, including non-breaking spaces, with a single space and trims the text.
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split(|c: char| c.is_whitespace() || c == '\u{00A0}' || c == '\u{200B}')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Computes the Levenshtein edit distance between `a` and `b` by characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[b.len()]
+}
+
+/// Computes the Jaro-Winkler similarity between `a` and `b`, from `0.0` (different) to `1.0` (equal).
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    } else if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0usize;
+    for i in 0..a.len() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        for j in start..end {
+            if !b_matched[j] && a[i] == b[j] {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let mut transpositions = 0usize;
+    let mut j = 0;
+    for i in 0..a.len() {
+        if a_matched[i] {
+            while !b_matched[j] {
+                j += 1;
+            }
+            if a[i] != b[j] {
+                transpositions += 1;
+            }
+            j += 1;
+        }
+    }
+
+    let m = matches as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - (transpositions / 2) as f64) / m) / 3.0;
+    let prefix = a.iter().zip(b.iter()).take(4).take_while(|(x, y)| x == y).count() as f64;
+
+    jaro + prefix * 0.1 * (1.0 - jaro)
+}
+
+/// Defines the algorithm to measure the similarity of two names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Similarity {
+    /// `1 - distance / max_length` by the Levenshtein edit distance.
+    #[default]
+    Levenshtein,
+    /// The Jaro-Winkler similarity, which favors the names with the same prefix.
+    JaroWinkler
+}
+
+impl Similarity {
+    /// Computes the similarity between `a` and `b`, from `0.0` (different) to `1.0` (equal).
+    pub fn score(&self, a: &str, b: &str) -> f64 {
+        match self {
+            Self::Levenshtein => {
+                let max_len = a.chars().count().max(b.chars().count());
+                if max_len == 0 {
+                    1.0
+                } else {
+                    1.0 - levenshtein(a, b) as f64 / max_len as f64
+                }
+            },
+            Self::JaroWinkler => jaro_winkler(a, b)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NameNormalizer;
+    use super::NormalizeStep;
+    use super::Similarity;
+    use super::collapse_whitespace;
+    use super::jaro_winkler;
+    use super::levenshtein;
+    use super::nfkc;
+    use super::strip_accelerator;
+    use super::strip_ellipsis;
+    use super::strip_shortcut;
+
+    #[test]
+    fn test_normalize_steps() {
+        assert_eq!(strip_accelerator("&Open"), "Open");
+        assert_eq!(strip_accelerator("Save && Exit"), "Save & Exit");
+        assert_eq!(strip_accelerator("文件(&F)"), "文件");
+        assert_eq!(strip_shortcut("Save\tCtrl+S"), "Save");
+        assert_eq!(strip_ellipsis("Save As..."), "Save As");
+        assert_eq!(strip_ellipsis("Print…"), "Print");
+        assert_eq!(strip_ellipsis("OK"), "OK");
+        assert_eq!(collapse_whitespace(" Page\u{00A0}\u{00A0}Setup \t"), "Page Setup");
+        assert_eq!(nfkc("ＯＫ（Ｏ）"), "OK(O)");
+    }
+
+    #[test]
+    fn test_normalizer() {
+        let normalizer = NameNormalizer::default();
+        assert_eq!(normalizer.normalize("Save &As...\tCtrl+Shift+S"), "save as");
+        assert_eq!(normalizer.normalize("ＦＩＬＥ(&F)"), "file");
+
+        let normalizer = NameNormalizer::new().step(NormalizeStep::StripAccelerator);
+        assert_eq!(normalizer.normalize("&Open..."), "Open...");
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("设置", "设置"), 0);
+        assert!((jaro_winkler("MARTHA", "MARHTA") - 0.9611).abs() < 0.001);
+        assert_eq!(jaro_winkler("abc", "xyz"), 0.0);
+        assert_eq!(Similarity::Levenshtein.score("Notepad", "Notepad"), 1.0);
+        assert!(Similarity::Levenshtein.score("Settings", "Setting") > 0.8);
+        assert!(Similarity::JaroWinkler.score("Check for updates", "Check for update") > 0.9);
+    }
+}
@@ -163,6 +163,36 @@ impl Rect {
     pub fn get_height(&self) -> i32 {
         self.0.bottom - self.0.top + 1
     }
+
+    /// Retrieves the center point of the rect.
+    pub fn get_center(&self) -> Point {
+        Point::new((self.0.left + self.0.right) / 2, (self.0.top + self.0.bottom) / 2)
+    }
+
+    /// Checks whether `other` is completely inside this rect.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.0.left >= self.0.left && other.0.right <= self.0.right && other.0.top >= self.0.top && other.0.bottom <= self.0.bottom
+    }
+
+    /// Checks whether this rect and `other` overlap horizontally, that is, they share some columns.
+    ///
+    /// The right and bottom edges are exclusive, the same as the Win32 `RECT` reported by UI Automation.
+    pub fn overlaps_horizontally(&self, other: &Rect) -> bool {
+        self.0.left < other.0.right && other.0.left < self.0.right
+    }
+
+    /// Checks whether this rect and `other` overlap vertically, that is, they share some rows.
+    pub fn overlaps_vertically(&self, other: &Rect) -> bool {
+        self.0.top < other.0.bottom && other.0.top < self.0.bottom
+    }
+
+    /// Retrieves the shortest distance between the edges of this rect and `other`, in the pixels between them.
+    /// The distance is `0` when the rects overlap or are adjacent.
+    pub fn distance_to(&self, other: &Rect) -> f64 {
+        let dx = (self.0.left - other.0.right).max(other.0.left - self.0.right).max(0) as f64;
+        let dy = (self.0.top - other.0.bottom).max(other.0.top - self.0.bottom).max(0) as f64;
+        (dx * dx + dy * dy).sqrt()
+    }
 }
 
 impl Eq for Rect {
@@ -209,6 +239,9 @@ impl AsMut<RECT> for Rect {
 #[derive(Default, Clone, Copy)]
 pub struct Handle(HANDLE);
 
+// SAFETY: a handle is an opaque value which is valid in the whole process, and it is only passed to the windows APIs.
+unsafe impl Send for Handle {}
+
 impl Handle {
     /// Checks if the handle is invalid.
     pub fn is_invalid(&self) -> bool {
@@ -1179,6 +1212,64 @@ pub enum StructureChangeType {
     ChildrenReordered = 5i32
 }
 
+/// `NotificationKind` is an enum wrapper for `windows::Win32::UI::Accessibility::NotificationKind`.
+/// 
+/// Defines values that indicate the type of a notification event, and a hint to the listener about the processing of the event.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumConvert)]
+#[map_as(windows::Win32::UI::Accessibility::NotificationKind)]
+pub enum NotificationKind {
+    /// The current element and/or the container has had something added to it that should be presented to the user.
+    ItemAdded = 0i32,
+    /// The current element has had something removed from inside of it that should be presented to the user.
+    ItemRemoved = 1i32,
+    /// The current element has a notification that an action was completed.
+    ActionCompleted = 2i32,
+    /// The current element has a notification that an action was aborted.
+    ActionAborted = 3i32,
+    /// The current element has a notification not an add, remove, completed, or aborted action.
+    Other = 4i32
+}
+
+/// `NotificationProcessing` is an enum wrapper for `windows::Win32::UI::Accessibility::NotificationProcessing`.
+/// 
+/// Defines values that indicate how a notification should be processed.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumConvert)]
+#[map_as(windows::Win32::UI::Accessibility::NotificationProcessing)]
+pub enum NotificationProcessing {
+    /// These notifications should be presented to the user as soon as possible and all of the notifications from this source should be delivered to the user.
+    ImportantAll = 0i32,
+    /// These notifications should be presented to the user as soon as possible. The most recent notification from this source should replace all other notifications from this source.
+    ImportantMostRecent = 1i32,
+    /// These notifications should all be presented to the user when possible.
+    All = 2i32,
+    /// These notifications should be presented to the user when possible. The most recent notification from this source should replace all other notifications from this source.
+    MostRecent = 3i32,
+    /// These notifications should be presented to the user when possible. Don't interrupt the current notification for this one.
+    /// If new notifications come in from the same source while the current notification is being presented, then keep the most recent and ignore the rest until the current processing is completed.
+    CurrentThenMostRecent = 4i32
+}
+
+/// `TextEditChangeType` is an enum wrapper for `windows::Win32::UI::Accessibility::TextEditChangeType`.
+/// 
+/// Describes the text editing change being performed by controls when text-edit events are raised or handled.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumConvert)]
+#[map_as(windows::Win32::UI::Accessibility::TextEditChangeType)]
+pub enum TextEditChangeType {
+    /// Not related to a specific change type.
+    None = 0i32,
+    /// Change is from an auto-correct action performed by a control.
+    AutoCorrect = 1i32,
+    /// Change is from an IME active composition within a control.
+    Composition = 2i32,
+    /// Change is from an IME composition going from active to finalized state within a control.
+    CompositionFinalized = 3i32,
+    /// Change is from an auto-complete action performed by a control.
+    AutoComplete = 4i32
+}
+
 /// Defines enum for `windows::Win32::UI::Accessibility::UIA_CONTROLTYPE_ID`.
 /// 
 /// Contains the named constants used to identify Microsoft UI Automation control types.
@@ -1274,6 +1365,8 @@ pub enum ControlType {
 mod tests {
     use windows::Win32::UI::Accessibility;
 
+    use super::Point;
+    use super::Rect;
     use super::WindowInteractionState;
 
     #[test]
@@ -1291,6 +1384,46 @@ mod tests {
         assert_eq!(running, "Running");
     }
 
+    #[test]
+    fn test_rect_geometry() {
+        let a = Rect::new(0, 0, 100, 20);
+        let b = Rect::new(130, 5, 200, 25);
+        let c = Rect::new(10, 60, 90, 80);
+
+        assert_eq!(a.get_center(), Point::new(50, 10));
+        assert!(a.overlaps_vertically(&b));
+        assert!(!a.overlaps_horizontally(&b));
+        assert!(a.overlaps_horizontally(&c));
+        assert_eq!(a.distance_to(&b), 30.0);
+        assert_eq!(a.distance_to(&c), 40.0);
+        assert_eq!(b.distance_to(&c), 50.0);
+        assert_eq!(a.distance_to(&a), 0.0);
+        assert!(Rect::new(0, 0, 200, 100).contains_rect(&c));
+        assert!(!c.contains_rect(&a));
+    }
+
+    #[test]
+    fn test_rect_edges() {
+        // the rect covers the columns and rows from 0 to 9, as the right and bottom edges are exclusive.
+        let a = Rect::new(0, 0, 10, 10);
+
+        // the rects sharing the last column or row overlap.
+        let shared = Rect::new(9, 9, 20, 20);
+        assert!(a.overlaps_horizontally(&shared));
+        assert!(a.overlaps_vertically(&shared));
+        assert_eq!(a.distance_to(&shared), 0.0);
+
+        // the adjacent rects do not overlap, and there is no pixel between them.
+        let adjacent = Rect::new(10, 10, 20, 20);
+        assert!(!a.overlaps_horizontally(&adjacent));
+        assert!(!a.overlaps_vertically(&adjacent));
+        assert_eq!(a.distance_to(&adjacent), 0.0);
+        assert_eq!(a.distance_to(&Rect::new(11, 0, 20, 10)), 1.0);
+
+        assert!(a.contains_rect(&Rect::new(9, 9, 10, 10)));
+        assert!(!a.contains_rect(&Rect::new(0, 0, 11, 10)));
+    }
+
     #[test]
     fn test_handle() {
         let handle = crate::types::Handle::from(0x001);
@@ -193,6 +193,35 @@ impl Variant {
         self.try_into()
     }
 
+    /// Retrieves the value as a copy, which keeps valid after the memory of the variant is freed.
+    ///
+    /// The variants passed in by UI Automation callbacks are freed after the callbacks return, so their values are copied before kept.
+    pub(crate) fn get_owned_value(&self) -> Result<Value> {
+        let vt = self.vt();
+        if vt == VT_SAFEARRAY {
+            let array = unsafe {
+                self.get_data().parray
+            };
+            if array.is_null() {
+                return Ok(Value::NULL);
+            }
+            let array = unsafe {
+                SafeArrayCopy(array)?
+            };
+            Ok(Value::SAFEARRAY(SafeArray::from(array)))
+        } else if vt == VT_VARIANT {
+            let value = unsafe {
+                self.get_data().pvarVal
+            };
+            if value.is_null() {
+                return Ok(Value::NULL);
+            }
+            Variant::from(unsafe { &*value }).get_owned_value()
+        } else {
+            self.get_value()
+        }
+    }
+
     /// Check whether the variant is null.
     /// 
     /// Return `true` when vt is `VT_EMPTY`, `VT_NULL` or `VT_VOID`.
@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use uiautomation::asyncs::AsyncAutomation;
 use uiautomation::UIAutomation;
 
 #[tokio::main]
@@ -14,4 +17,15 @@ async fn main() {
     let result = handler.await;
 
     println!("Got: {:?}", result);
-}
\ No newline at end of file
+
+    let automation = AsyncAutomation::new().unwrap();
+    let notepad = automation.find_first_async(|a| a.create_matcher().classname("Notepad").timeout(30000));
+
+    tokio::select! {
+        found = notepad => match found {
+            Ok(notepad) => println!("Found notepad: {}", notepad.get_name().unwrap()),
+            Err(e) => println!("Failed to find notepad: {}", e),
+        },
+        _ = tokio::time::sleep(Duration::from_secs(5)) => println!("Gave up waiting for notepad."),
+    }
+}
@@ -44,12 +44,12 @@ fn main() {
                 let structure_changed_handler = MyStructureChangeEventHandler {};
                 let structure_changed_handler = UIStructureChangeEventHandler::from(structure_changed_handler);
 
-                automation.add_structure_changed_event_handler(&notepad, uiautomation::types::TreeScope::Subtree, None, &structure_changed_handler).unwrap();
+                automation.add_structure_changed_event_handler(&notepad, uiautomation::types::TreeScope::Subtree, None, &structure_changed_handler).unwrap().detach();
 
                 let focus_changed_handler = MyFocusChangedEventHandler {};
                 let focus_changed_handler = UIFocusChangedEventHandler::from(focus_changed_handler);
 
-                automation.add_focus_changed_event_handler(None, &focus_changed_handler).unwrap();
+                automation.add_focus_changed_event_handler(None, &focus_changed_handler).unwrap().detach(); // keep the handlers after this thread exits
 
                 println!("try to do something with 'notepad'...");
             },
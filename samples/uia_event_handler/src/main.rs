@@ -32,11 +32,12 @@ fn main() {
     let automation = UIAutomation::new().unwrap();
     let root = automation.get_root_element().unwrap();
     let matcher = automation.create_matcher().from(root).timeout(10000).classname("Notepad");
+    let mut registrations = Vec::new();
     if let Ok(notepad) = matcher.find_first() {
         let focus_changed_handler = MyFocusChangedEventHandler {};
         let focus_changed_handler = UIFocusChangedEventHandler::from(focus_changed_handler);
 
-        automation.add_focus_changed_event_handler(None, &focus_changed_handler).unwrap();
+        registrations.push(automation.add_focus_changed_event_handler(None, &focus_changed_handler).unwrap());
 
         let text_changed_handler: Box<CustomPropertyChangedEventHandlerFn> = Box::new(|sender, property, value| {
             println!("Property changed: {}.{:?} = {}", sender, property, value);
@@ -44,14 +45,17 @@ fn main() {
         });
         let text_changed_handler = UIPropertyChangedEventHandler::from(text_changed_handler);
 
-        automation.add_property_changed_event_handler(&notepad, uiautomation::types::TreeScope::Subtree, None, &text_changed_handler, &[UIProperty::ValueValue]).unwrap();
+        registrations.push(automation.add_property_changed_event_handler(&notepad, uiautomation::types::TreeScope::Subtree, None, &text_changed_handler, &[UIProperty::ValueValue]).unwrap());
 
         let structure_changed_handler = MyStructureChangeEventHandler {};
         let structure_changed_handler = UIStructureChangeEventHandler::from(structure_changed_handler);
 
-        automation.add_structure_changed_event_handler(&notepad, uiautomation::types::TreeScope::Subtree, None, &structure_changed_handler).unwrap();
+        registrations.push(automation.add_structure_changed_event_handler(&notepad, uiautomation::types::TreeScope::Subtree, None, &structure_changed_handler).unwrap());
     }
 
     println!("waiting for notepad.exe...");
     note_proc.wait().unwrap();
+
+    // the event handlers are removed when the registrations are dropped.
+    drop(registrations);
 }